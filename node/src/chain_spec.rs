@@ -1,13 +1,17 @@
 use node_template_runtime::{
-	AccountId, AuraConfig, BalancesConfig, Erc20Config, GenesisConfig, GrandpaConfig, Signature,
-	SudoConfig, SystemConfig, WASM_BINARY,
+	AccountId, AuraConfig, BalancesConfig, Decimals, Erc20Config, GenesisConfig, GrandpaConfig,
+	NativeDecimals, SS58Prefix, Signature, SudoConfig, Symbol, SystemConfig, WASM_BINARY,
 };
-use sc_service::ChainType;
+use sc_service::{config::MultiaddrWithPeerId, ChainType, Properties};
+use serde::Deserialize;
+use serde_json::json;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{sr25519, Pair, Public};
+use sp_core::{crypto::Ss58Codec, ed25519, sr25519, Pair, Public, U256};
 use sp_finality_grandpa::AuthorityId as GrandpaId;
 use sp_runtime::traits::{IdentifyAccount, Verify};
 
+use crate::erc20_genesis;
+
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
 
@@ -36,7 +40,10 @@ pub fn authority_keys_from_seed(s: &str) -> (AuraId, GrandpaId) {
 	(get_from_seed::<AuraId>(s), get_from_seed::<GrandpaId>(s))
 }
 
-pub fn development_config() -> Result<ChainSpec, String> {
+/// `erc20_allocations` replace the default ERC20 balances of the pre-funded accounts.
+pub fn development_config(
+	erc20_allocations: Option<Vec<(AccountId, U256)>>,
+) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
 	Ok(ChainSpec::from_genesis(
@@ -59,6 +66,7 @@ pub fn development_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Alice//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Bob//stash"),
 				],
+				erc20_allocations.clone(),
 				true,
 			)
 		},
@@ -70,13 +78,16 @@ pub fn development_config() -> Result<ChainSpec, String> {
 		None,
 		None,
 		// Properties
-		None,
+		Some(properties()),
 		// Extensions
 		None,
 	))
 }
 
-pub fn local_testnet_config() -> Result<ChainSpec, String> {
+/// `erc20_allocations` replace the default ERC20 balances of the pre-funded accounts.
+pub fn local_testnet_config(
+	erc20_allocations: Option<Vec<(AccountId, U256)>>,
+) -> Result<ChainSpec, String> {
 	let wasm_binary = WASM_BINARY.ok_or_else(|| "Development wasm not available".to_string())?;
 
 	Ok(ChainSpec::from_genesis(
@@ -107,6 +118,7 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 					get_account_id_from_seed::<sr25519::Public>("Eve//stash"),
 					get_account_id_from_seed::<sr25519::Public>("Ferdie//stash"),
 				],
+				erc20_allocations.clone(),
 				true,
 			)
 		},
@@ -116,22 +128,174 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
 		None,
 		// Protocol ID
 		None,
+		None,
 		// Properties
+		Some(properties()),
+		// Extensions
 		None,
+	))
+}
+
+/// Configuration of the staging chain, read from a JSON file.
+///
+/// Every key and account is an SS58 address, the authorities are given as their sr25519 (Aura) and
+/// ed25519 (GRANDPA) public keys.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StagingConfig {
+	/// Name of the chain
+	pub name: String,
+	/// ID of the chain, also used as the name of its directory
+	pub id: String,
+	/// Initial PoA authorities
+	pub authorities: Vec<StagingAuthority>,
+	/// Sudo account
+	pub sudo: String,
+	/// Accounts pre-funded with native tokens
+	pub endowed_accounts: Vec<String>,
+	/// Bootnodes, as multiaddresses ending with `/p2p/<peer id>`
+	#[serde(default)]
+	pub boot_nodes: Vec<String>,
+}
+
+/// Session keys of an authority of the staging chain
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StagingAuthority {
+	/// sr25519 key used to author blocks
+	pub aura: String,
+	/// ed25519 key used to finalize blocks
+	pub grandpa: String,
+}
+
+impl StagingConfig {
+	/// Reads the configuration from a JSON file.
+	pub fn from_json_file(path: &std::path::Path) -> Result<Self, String> {
+		let file = std::fs::File::open(path)
+			.map_err(|e| format!("Unable to open {}: {}", path.display(), e))?;
+
+		serde_json::from_reader(file).map_err(|e| format!("Invalid {}: {}", path.display(), e))
+	}
+}
+
+/// Staging chain, without any well-known key: the authorities, sudo and pre-funded accounts come
+/// from `config` and the ERC20 balances from `erc20_allocations`.
+pub fn staging_config(
+	config: StagingConfig,
+	erc20_allocations: Vec<(AccountId, U256)>,
+) -> Result<ChainSpec, String> {
+	let wasm_binary = WASM_BINARY.ok_or_else(|| "Staging wasm not available".to_string())?;
+
+	if config.authorities.is_empty() {
+		return Err("The staging chain needs at least one authority".into())
+	}
+	let authorities = config
+		.authorities
+		.iter()
+		.map(|authority| {
+			Ok((
+				AuraId::from(parse_ss58::<sr25519::Public>(&authority.aura)?),
+				GrandpaId::from(parse_ss58::<ed25519::Public>(&authority.grandpa)?),
+			))
+		})
+		.collect::<Result<Vec<_>, String>>()?;
+	let root_key = parse_ss58::<AccountId>(&config.sudo)?;
+	let endowed_accounts = config
+		.endowed_accounts
+		.iter()
+		.map(|account| parse_ss58::<AccountId>(account))
+		.collect::<Result<Vec<_>, _>>()?;
+	let boot_nodes = config
+		.boot_nodes
+		.iter()
+		.map(|boot_node| {
+			boot_node
+				.parse::<MultiaddrWithPeerId>()
+				.map_err(|e| format!("Invalid bootnode `{}`: {}", boot_node, e))
+		})
+		.collect::<Result<Vec<_>, _>>()?;
+
+	Ok(ChainSpec::from_genesis(
+		// Name
+		&config.name,
+		// ID
+		&config.id,
+		ChainType::Live,
+		move || {
+			testnet_genesis(
+				wasm_binary,
+				authorities.clone(),
+				root_key.clone(),
+				endowed_accounts.clone(),
+				Some(erc20_allocations.clone()),
+				false,
+			)
+		},
+		// Bootnodes
+		boot_nodes,
+		// Telemetry
 		None,
+		// Protocol ID
+		Some(&config.id),
+		None,
+		// Properties
+		Some(properties()),
 		// Extensions
 		None,
 	))
 }
 
+/// Symbol of the native token, which the runtime doesn't define
+const NATIVE_SYMBOL: &str = "UNIT";
+
+/// Properties of the chain, used by the wallets and explorers to display the native and ERC20
+/// tokens.
+///
+/// `tokenSymbol` and `tokenDecimals` list the native token first, as the UIs expect, and the ERC20
+/// token second. The `erc20` object describes the ERC20 token on its own, and `types` holds the
+/// custom type definitions of its amounts, which are `U256` in the smallest units.
+fn properties() -> Properties {
+	let properties = json!({
+		"tokenSymbol": [NATIVE_SYMBOL, Symbol::get()],
+		"tokenDecimals": [NativeDecimals::get(), Decimals::get()],
+		"ss58Format": SS58Prefix::get(),
+		"erc20": {
+			"name": node_template_runtime::Name::get(),
+			"symbol": Symbol::get(),
+			"decimals": Decimals::get(),
+			"pallet": "Erc20",
+			"amountType": "Erc20Amount",
+		},
+		"types": {
+			"Erc20Amount": "U256",
+			"Erc20Holder": { "account": "AccountId", "balance": "Erc20Amount" },
+		},
+	});
+
+	match properties {
+		serde_json::Value::Object(properties) => properties,
+		_ => unreachable!("the properties are an object; qed"),
+	}
+}
+
+fn parse_ss58<T: Ss58Codec>(address: &str) -> Result<T, String> {
+	T::from_ss58check(address).map_err(|e| format!("Invalid address `{}`: {:?}", address, e))
+}
+
 /// Configure initial storage state for FRAME modules.
 fn testnet_genesis(
 	wasm_binary: &[u8],
 	initial_authorities: Vec<(AuraId, GrandpaId)>,
 	root_key: AccountId,
 	endowed_accounts: Vec<AccountId>,
+	erc20_allocations: Option<Vec<(AccountId, U256)>>,
 	_enable_println: bool,
 ) -> GenesisConfig {
+	// Pre-funded accounts get 1 << 60 tokens unless the allocations are given.
+	let erc20_balances = erc20_allocations.unwrap_or_else(|| {
+		endowed_accounts.iter().cloned().map(|k| (k, (1u64 << 60).into())).collect()
+	});
+
 	GenesisConfig {
 		system: SystemConfig {
 			// Add Wasm runtime to storage.
@@ -153,8 +317,9 @@ fn testnet_genesis(
 		},
 		transaction_payment: Default::default(),
 		erc_20: Erc20Config {
-			total_supply: ((1 << 60) * endowed_accounts.len() as u128).into(),
-			balances: endowed_accounts.iter().cloned().map(|k| (k, (1u64 << 60).into())).collect(),
+			total_supply: erc20_genesis::total_supply(&erc20_balances),
+			balances: erc20_balances,
+			claims: vec![],
 		},
 	}
 }
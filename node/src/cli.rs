@@ -1,4 +1,6 @@
+use crate::{indexer::IndexerConfig, rpc::bridge::BridgeConfig};
 use sc_cli::RunCmd;
+use std::path::PathBuf;
 
 #[derive(Debug, clap::Parser)]
 pub struct Cli {
@@ -7,6 +9,67 @@ pub struct Cli {
 
 	#[clap(flatten)]
 	pub run: RunCmd,
+
+	#[clap(flatten)]
+	pub erc20_indexer: Erc20IndexerParams,
+
+	#[clap(flatten)]
+	pub erc20_rpc: Erc20RpcParams,
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Erc20IndexerParams {
+	/// Index the ERC20 transfers of the finalized blocks by account, to serve
+	/// `erc20_transfersByAccount`. Requires `--pruning archive` (or `archive-canonical`).
+	#[clap(long)]
+	pub enable_erc20_indexer: bool,
+
+	/// Drop the ERC20 transfers index and rebuild it from block 0.
+	#[clap(long, requires = "enable-erc20-indexer")]
+	pub erc20_indexer_rebuild: bool,
+}
+
+impl Erc20IndexerParams {
+	/// Indexer options, `None` if the indexer is disabled
+	pub fn config(&self) -> Option<IndexerConfig> {
+		self.enable_erc20_indexer
+			.then(|| IndexerConfig { rebuild: self.erc20_indexer_rebuild })
+	}
+}
+
+#[derive(Debug, clap::Args)]
+pub struct Erc20RpcParams {
+	/// Maximum number of ERC20 RPC calls per second on each connection to an RPC endpoint denying
+	/// unsafe calls (see `--rpc-methods`), unlimited if 0.
+	#[clap(long, value_name = "COUNT", default_value = "20")]
+	pub erc20_rpc_rate_limit: u32,
+
+	/// Comma separated list of the ERC20 RPC methods served by the RPC endpoints denying unsafe
+	/// calls, e.g. `erc20_holders,erc20_subscribeBalance` (all of them by default).
+	#[clap(long, value_name = "METHODS", use_value_delimiter = true)]
+	pub erc20_rpc_methods: Vec<String>,
+}
+
+impl Erc20RpcParams {
+	/// Access rules of the ERC20 methods on the public endpoints
+	pub fn config(&self) -> BridgeConfig {
+		BridgeConfig {
+			rate_limit: self.erc20_rpc_rate_limit,
+			methods: (!self.erc20_rpc_methods.is_empty())
+				.then(|| self.erc20_rpc_methods.iter().cloned().collect()),
+		}
+	}
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct BuildSpecCmd {
+	#[clap(flatten)]
+	pub inner: sc_cli::BuildSpecCmd,
+
+	/// CSV or JSON file of the ERC20 genesis allocations, replacing the balances of the pre-funded
+	/// accounts. Only applies to the `dev` and `local` chains.
+	#[clap(long, value_name = "FILE")]
+	pub erc20_allocations: Option<PathBuf>,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -16,7 +79,10 @@ pub enum Subcommand {
 	Key(sc_cli::KeySubcommand),
 
 	/// Build a chain specification.
-	BuildSpec(sc_cli::BuildSpecCmd),
+	BuildSpec(BuildSpecCmd),
+
+	/// Generate the raw and human-readable specifications of the staging chain.
+	GenerateSpec(crate::generate_spec::GenerateSpecCmd),
 
 	/// Validate blocks.
 	CheckBlock(sc_cli::CheckBlockCmd),
@@ -36,6 +102,9 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Inspect the state of the ERC20 pallet from the database.
+	Erc20(crate::erc20_cmd::Erc20Cmd),
+
 	/// The custom benchmark subcommand benchmarking runtime pallets.
 	#[clap(name = "benchmark", about = "Benchmark runtime pallets.")]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
@@ -1,7 +1,8 @@
 use crate::{
 	chain_spec,
 	cli::{Cli, Subcommand},
-	service,
+	erc20_cmd::{self, Erc20Query},
+	erc20_genesis, service,
 };
 use node_template_runtime::Block;
 use sc_cli::{ChainSpec, RuntimeVersion, SubstrateCli};
@@ -33,9 +34,20 @@ impl SubstrateCli for Cli {
 	}
 
 	fn load_spec(&self, id: &str) -> Result<Box<dyn sc_service::ChainSpec>, String> {
+		let erc20_allocations = match &self.subcommand {
+			Some(Subcommand::BuildSpec(cmd)) => cmd
+				.erc20_allocations
+				.as_deref()
+				.map(erc20_genesis::load_allocations)
+				.transpose()?,
+			_ => None,
+		};
+
 		Ok(match id {
-			"dev" => Box::new(chain_spec::development_config()?),
-			"" | "local" => Box::new(chain_spec::local_testnet_config()?),
+			"dev" => Box::new(chain_spec::development_config(erc20_allocations)?),
+			"" | "local" => Box::new(chain_spec::local_testnet_config(erc20_allocations)?),
+			_ if erc20_allocations.is_some() =>
+				return Err("--erc20-allocations only applies to the dev and local chains".into()),
 			path =>
 				Box::new(chain_spec::ChainSpec::from_json_file(std::path::PathBuf::from(path))?),
 		})
@@ -53,9 +65,10 @@ pub fn run() -> sc_cli::Result<()> {
 	match &cli.subcommand {
 		Some(Subcommand::Key(cmd)) => cmd.run(&cli),
 		Some(Subcommand::BuildSpec(cmd)) => {
-			let runner = cli.create_runner(cmd)?;
-			runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
+			let runner = cli.create_runner(&cmd.inner)?;
+			runner.sync_run(|config| cmd.inner.run(config.chain_spec, config.network))
 		},
+		Some(Subcommand::GenerateSpec(cmd)) => cmd.run(),
 		Some(Subcommand::CheckBlock(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			runner.async_run(|config| {
@@ -98,6 +111,17 @@ pub fn run() -> sc_cli::Result<()> {
 				Ok((cmd.run(client, backend), task_manager))
 			})
 		},
+		Some(Subcommand::Erc20(cmd)) => match &cmd.query {
+			// transactions are sent to a running node, so the database isn't opened
+			Erc20Query::Tx(tx) => tx.run(),
+			_ => {
+				let runner = cli.create_runner(cmd)?;
+				runner.sync_run(|mut config| {
+					erc20_cmd::open_read_only(&mut config)?;
+					cmd.run(service::new_client(&config)?)
+				})
+			},
+		},
 		Some(Subcommand::Benchmark(cmd)) =>
 			if cfg!(feature = "runtime-benchmarks") {
 				let runner = cli.create_runner(cmd)?;
@@ -128,7 +152,8 @@ pub fn run() -> sc_cli::Result<()> {
 		None => {
 			let runner = cli.create_runner(&cli.run)?;
 			runner.run_node_until_exit(|config| async move {
-				service::new_full(config).map_err(sc_cli::Error::Service)
+				service::new_full(config, cli.erc20_indexer.config(), cli.erc20_rpc.config())
+					.map_err(sc_cli::Error::Service)
 			})
 		},
 	}
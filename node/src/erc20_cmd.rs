@@ -0,0 +1,152 @@
+//! `erc20` subcommand, to inspect the state of the erc20 pallet straight from the local database.
+
+use std::sync::Arc;
+
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use node_template_runtime::{opaque::Block, AccountId, Hash};
+use sc_cli::{CliConfiguration, DatabaseParams, PruningParams, SharedParams};
+use sc_client_api::{Backend, StorageProvider};
+use sc_service::{
+	config::{DatabaseSource, KeystoreConfig},
+	Configuration,
+};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::generic::BlockId;
+
+use crate::{erc20_storage, erc20_tx::Erc20TxCmd};
+
+/// Number of columns of the client database, see `sc_client_db::columns`
+const CLIENT_DB_COLUMNS: u32 = 13;
+
+/// The `erc20` command used to inspect the state of the erc20 pallet.
+///
+/// The queries read the chain at the best block unless `--at` is given, from the database opened
+/// read-only by `open_read_only`. Transactions (`tx`) go through RPC instead and are sent with
+/// `Erc20TxCmd::run`, without opening the database.
+#[derive(Debug, clap::Parser)]
+pub struct Erc20Cmd {
+	#[clap(subcommand)]
+	pub query: Erc20Query,
+
+	/// Hash of the block to inspect, the best block by default.
+	#[clap(long, value_name = "HASH", global = true)]
+	pub at: Option<Hash>,
+
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum Erc20Query {
+	/// Print the balance of an account.
+	Balance {
+		/// SS58 address (or hex public key) of the account.
+		account: AccountId,
+	},
+
+	/// Print the total supply.
+	Supply,
+
+	/// Print the allowance of a spender over the tokens of an owner.
+	Allowance {
+		/// SS58 address (or hex public key) of the owner.
+		owner: AccountId,
+		/// SS58 address (or hex public key) of the spender.
+		spender: AccountId,
+	},
+
+	/// Print all the accounts with a non-zero balance.
+	Holders {
+		/// Print the holders as CSV, with an `account,balance` header.
+		#[clap(long)]
+		csv: bool,
+	},
+
+	/// Build, sign and submit a transaction (through RPC, the database isn't used).
+	Tx(Erc20TxCmd),
+}
+
+impl Erc20Cmd {
+	/// Run the command
+	pub fn run<C, B>(&self, client: Arc<C>) -> sc_cli::Result<()>
+	where
+		B: Backend<Block>,
+		C: HeaderBackend<Block> + StorageProvider<Block, B>,
+	{
+		let at = BlockId::Hash(self.at.unwrap_or_else(|| client.info().best_hash));
+
+		match &self.query {
+			Erc20Query::Balance { account } => {
+				let key = erc20_storage::balance_key(account);
+				println!("{}", erc20_storage::read_u256(&*client, &at, &key)?);
+			},
+			Erc20Query::Supply => {
+				let key = erc20_storage::total_supply_key();
+				println!("{}", erc20_storage::read_u256(&*client, &at, &key)?);
+			},
+			Erc20Query::Allowance { owner, spender } => {
+				let key = erc20_storage::allowance_key(owner, spender);
+				println!("{}", erc20_storage::read_u256(&*client, &at, &key)?);
+			},
+			Erc20Query::Holders { csv } => {
+				if *csv {
+					println!("account,balance");
+				}
+				for (account, balance) in erc20_storage::holders(&*client, &at)? {
+					if *csv {
+						println!("{},{}", account, balance);
+					} else {
+						println!("{} {}", account, balance);
+					}
+				}
+			},
+			// sent by `command.rs` before the database is opened
+			Erc20Query::Tx(_) => unreachable!("transactions don't read the database; qed"),
+		}
+
+		Ok(())
+	}
+}
+
+/// Switches `config` to a read-only access to the database: a RocksDB secondary instance of it,
+/// which can be opened while the node runs, and an in-memory keystore.
+pub fn open_read_only(config: &mut Configuration) -> sc_cli::Result<()> {
+	let path = match &config.database {
+		DatabaseSource::RocksDb { path, .. } => path.clone(),
+		DatabaseSource::Auto { rocksdb_path, .. } => rocksdb_path.clone(),
+		_ => return Err("The erc20 command only reads RocksDB databases".into()),
+	};
+	// the secondary instance keeps its own logs, out of the database of the node
+	let secondary = std::env::temp_dir().join(format!("erc20-cmd-{}", std::process::id()));
+	let db_config = DatabaseConfig {
+		secondary: Some(secondary.to_string_lossy().into()),
+		..DatabaseConfig::with_columns(CLIENT_DB_COLUMNS)
+	};
+	let db = Database::open(&db_config, &path.to_string_lossy())
+		.map_err(|e| format!("Unable to open the database at {}: {}", path.display(), e))?;
+
+	config.database = DatabaseSource::Custom(sp_database::as_database::<_, H256>(db));
+	config.keystore = KeystoreConfig::InMemory;
+	Ok(())
+}
+
+impl CliConfiguration for Erc20Cmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		Some(&self.pruning_params)
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		Some(&self.database_params)
+	}
+}
@@ -0,0 +1,99 @@
+//! ERC20 genesis allocations, loaded from a CSV or JSON file.
+//!
+//! CSV files hold one `account,amount` line per allocation (an `account,amount` header, blank
+//! lines and `#` comments are skipped). JSON files hold an array of `{"account", "amount"}`
+//! objects. Accounts are SS58 addresses and amounts are given in tokens, e.g. `1.5`.
+
+use std::{collections::HashSet, fs, path::Path};
+
+use node_template_runtime::AccountId;
+use serde::Deserialize;
+use sp_core::{crypto::Ss58Codec, U256};
+
+use crate::erc20_tx::parse_amount;
+
+/// Allocation as written in a JSON file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonAllocation {
+	account: String,
+	amount: String,
+}
+
+/// Reads and validates the allocations of `path`, in the order of the file.
+///
+/// Invalid addresses (including wrong checksums), invalid or zero amounts and accounts allocated
+/// twice are rejected, as well as allocations whose total overflows.
+pub fn load_allocations(path: &Path) -> Result<Vec<(AccountId, U256)>, String> {
+	let content = fs::read_to_string(path)
+		.map_err(|e| format!("Unable to read {}: {}", path.display(), e))?;
+
+	let entries = if path.extension().map_or(false, |ext| ext.eq_ignore_ascii_case("json")) {
+		serde_json::from_str::<Vec<JsonAllocation>>(&content)
+			.map_err(|e| format!("Invalid {}: {}", path.display(), e))?
+			.into_iter()
+			.enumerate()
+			.map(|(i, entry)| (format!("entry {}", i), entry.account, entry.amount))
+			.collect()
+	} else {
+		csv_entries(&content).map_err(|e| format!("{}, {}", path.display(), e))?
+	};
+
+	let mut seen = HashSet::new();
+	let mut total = U256::zero();
+	let mut allocations = Vec::with_capacity(entries.len());
+
+	for (position, account, amount) in entries {
+		let error = |e: String| format!("{}, {}: {}", path.display(), position, e);
+
+		let account = AccountId::from_ss58check(&account)
+			.map_err(|e| error(format!("invalid address `{}`: {:?}", account, e)))?;
+		let amount = parse_amount(&amount).map_err(error)?;
+		if amount.is_zero() {
+			return Err(error(format!("zero allocation to {}", account)))
+		}
+		if !seen.insert(account.clone()) {
+			return Err(error(format!("{} is allocated twice", account)))
+		}
+		total = total
+			.checked_add(amount)
+			.ok_or_else(|| error("the total supply overflows".into()))?;
+
+		allocations.push((account, amount));
+	}
+
+	if allocations.is_empty() {
+		return Err(format!("{} has no allocation", path.display()))
+	}
+
+	Ok(allocations)
+}
+
+/// Total supply of the allocations, which never overflows once they are loaded
+pub fn total_supply(allocations: &[(AccountId, U256)]) -> U256 {
+	allocations
+		.iter()
+		.fold(U256::zero(), |total, (_, amount)| total.saturating_add(*amount))
+}
+
+/// `(position, account, amount)` of the lines of a CSV file
+fn csv_entries(content: &str) -> Result<Vec<(String, String, String)>, String> {
+	let mut entries = vec![];
+
+	for (i, line) in content.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || line.starts_with('#') {
+			continue
+		}
+
+		let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+		match fields[..] {
+			["account", "amount"] if entries.is_empty() => continue,
+			[account, amount] =>
+				entries.push((format!("line {}", i + 1), account.into(), amount.into())),
+			_ => return Err(format!("line {}: expected `account,amount`", i + 1)),
+		}
+	}
+
+	Ok(entries)
+}
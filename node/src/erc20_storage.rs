@@ -0,0 +1,81 @@
+//! Direct access to the storage of the erc20 pallet, for the node-side code that reads the state
+//! without going through the runtime.
+
+use codec::{Decode, Encode};
+use node_template_runtime::{opaque::Block, AccountId};
+use sc_client_api::{Backend, StorageProvider};
+use sp_blockchain::{Error as BlockChainError, Result as BlockChainResult};
+use sp_core::{blake2_128, storage::StorageKey, twox_128, U256};
+use sp_runtime::generic::BlockId;
+
+/// Name of the erc20 pallet in the runtime
+const PALLET: &[u8] = b"Erc20";
+
+/// Key of the `TotalSupply` value
+pub fn total_supply_key() -> StorageKey {
+	StorageKey([twox_128(PALLET), twox_128(b"TotalSupply")].concat())
+}
+
+/// Prefix of the keys of the `Balance` map
+pub fn balance_prefix() -> StorageKey {
+	StorageKey([twox_128(PALLET), twox_128(b"Balance")].concat())
+}
+
+/// Key of the balance of `account`
+pub fn balance_key(account: &AccountId) -> StorageKey {
+	StorageKey([balance_prefix().0, blake2_128_concat(account)].concat())
+}
+
+/// Key of the allowance of `spender` over the tokens of `owner`
+pub fn allowance_key(owner: &AccountId, spender: &AccountId) -> StorageKey {
+	let prefix = [twox_128(PALLET), twox_128(b"Allowance")].concat();
+	StorageKey([prefix, blake2_128_concat(owner), blake2_128_concat(spender)].concat())
+}
+
+/// Decodes the account from the key of its balance
+pub fn balance_account(key: &StorageKey) -> Option<AccountId> {
+	// skip the prefix and the blake2-128 hash of the account
+	key.0.get(48..).and_then(|mut encoded| AccountId::decode(&mut encoded).ok())
+}
+
+/// Reads the `U256` value stored under `key` at block `at`, zero if there is none
+pub fn read_u256<C, B>(client: &C, at: &BlockId<Block>, key: &StorageKey) -> BlockChainResult<U256>
+where
+	B: Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	match client.storage(at, key)? {
+		Some(data) => U256::decode(&mut &data.0[..]).map_err(decode_error),
+		None => Ok(U256::zero()),
+	}
+}
+
+/// Accounts with a non-zero balance at block `at`, in storage order
+pub fn holders<C, B>(client: &C, at: &BlockId<Block>) -> BlockChainResult<Vec<(AccountId, U256)>>
+where
+	B: Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	let mut holders = vec![];
+
+	for (key, data) in client.storage_pairs(at, &balance_prefix())? {
+		let balance = U256::decode(&mut &data.0[..]).map_err(decode_error)?;
+		if balance.is_zero() {
+			continue
+		}
+		let account = balance_account(&key)
+			.ok_or_else(|| decode_error(format!("invalid balance key {:?}", key)))?;
+		holders.push((account, balance));
+	}
+
+	Ok(holders)
+}
+
+fn blake2_128_concat(account: &AccountId) -> Vec<u8> {
+	let encoded = account.encode();
+	[&blake2_128(&encoded)[..], &encoded].concat()
+}
+
+fn decode_error(err: impl std::fmt::Debug) -> BlockChainError {
+	BlockChainError::Backend(format!("Unable to decode ERC20 state: {:?}", err))
+}
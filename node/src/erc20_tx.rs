@@ -0,0 +1,275 @@
+//! `erc20 tx` subcommand, to build, sign and submit erc20 transactions.
+//!
+//! The nonce, genesis hash and runtime versions are fetched from the node unless they are given,
+//! so the transactions can also be built on an air-gapped machine: either signed there with
+//! `--suri` and `--print`, or only encoded with `--from` so the payload can be signed elsewhere and
+//! then assembled with `--from` and `--signature`.
+
+use codec::Encode;
+use jsonrpsee::{
+	core::client::ClientT,
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template_runtime::{
+	pallet_erc20, AccountId, Balance, Call, Decimals, Hash, Index, Runtime, Signature, SignedExtra,
+	UncheckedExtrinsic,
+};
+use serde::{de::DeserializeOwned, Deserialize};
+use sp_core::{crypto::Ss58Codec, hexdisplay::HexDisplay, sr25519, Bytes, Pair, U256};
+use sp_runtime::generic::{Era, SignedPayload};
+
+/// The `erc20 tx` command used to build, sign and submit erc20 transactions.
+#[derive(Debug, clap::Parser)]
+pub struct Erc20TxCmd {
+	#[clap(subcommand)]
+	pub call: Erc20Call,
+
+	/// Secret URI of the signing account, e.g. `//Alice` or a mnemonic phrase.
+	#[clap(long, value_name = "SURI", global = true)]
+	pub suri: Option<String>,
+
+	/// Address of the signing account, when the transaction is signed elsewhere. Without
+	/// `--signature` the payload to sign is printed.
+	#[clap(long, value_name = "SS58", global = true)]
+	pub from: Option<AccountId>,
+
+	/// Hex encoded sr25519 signature of the payload printed with `--from`.
+	#[clap(long, value_name = "HEX", global = true)]
+	pub signature: Option<String>,
+
+	/// WebSocket URL of the node.
+	#[clap(long, value_name = "URL", default_value = "ws://127.0.0.1:9944", global = true)]
+	pub url: String,
+
+	/// Print the signed transaction instead of submitting it.
+	#[clap(long, global = true)]
+	pub print: bool,
+
+	/// Nonce of the signing account, fetched from the node by default.
+	#[clap(long, global = true)]
+	pub nonce: Option<Index>,
+
+	/// Genesis hash of the chain, fetched from the node by default.
+	#[clap(long, value_name = "HASH", global = true)]
+	pub genesis_hash: Option<Hash>,
+
+	/// Spec version of the runtime, fetched from the node by default.
+	#[clap(long, global = true)]
+	pub spec_version: Option<u32>,
+
+	/// Transaction version of the runtime, fetched from the node by default.
+	#[clap(long, global = true)]
+	pub transaction_version: Option<u32>,
+
+	/// Tip for the block author, in the smallest units of the native token.
+	#[clap(long, default_value = "0", global = true)]
+	pub tip: Balance,
+}
+
+/// Amounts are given in tokens, e.g. `1.5`, and converted using the decimals of the runtime.
+#[derive(Debug, clap::Subcommand)]
+pub enum Erc20Call {
+	/// Transfer tokens of the signer.
+	Transfer {
+		/// Recipient of the tokens.
+		#[clap(long, value_name = "SS58")]
+		to: AccountId,
+		/// Amount of tokens to transfer.
+		#[clap(long)]
+		amount: String,
+	},
+
+	/// Set the allowance of a spender over the tokens of the signer.
+	Approve {
+		/// Account allowed to spend the tokens.
+		#[clap(long, value_name = "SS58")]
+		spender: AccountId,
+		/// Amount of tokens the spender is allowed to spend.
+		#[clap(long)]
+		amount: String,
+	},
+
+	/// Transfer tokens of an owner, using the allowance of the signer.
+	TransferFrom {
+		/// Owner of the tokens.
+		#[clap(long, value_name = "SS58")]
+		owner: AccountId,
+		/// Recipient of the tokens.
+		#[clap(long, value_name = "SS58")]
+		to: AccountId,
+		/// Amount of tokens to transfer.
+		#[clap(long)]
+		amount: String,
+	},
+}
+
+impl Erc20Call {
+	fn to_call(&self) -> Result<Call, String> {
+		let call = match self {
+			Self::Transfer { to, amount } =>
+				pallet_erc20::Call::transfer { to: to.clone(), amount: parse_amount(amount)? },
+			Self::Approve { spender, amount } => pallet_erc20::Call::approve {
+				spender: spender.clone(),
+				amount: parse_amount(amount)?,
+			},
+			Self::TransferFrom { owner, to, amount } => pallet_erc20::Call::transfer_from {
+				from: owner.clone(),
+				to: to.clone(),
+				amount: parse_amount(amount)?,
+			},
+		};
+
+		Ok(Call::Erc20(call))
+	}
+}
+
+/// Runtime versions, as returned by `state_getRuntimeVersion`
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+	spec_version: u32,
+	transaction_version: u32,
+}
+
+impl Erc20TxCmd {
+	/// Run the command
+	pub fn run(&self) -> sc_cli::Result<()> {
+		tokio::runtime::Builder::new_current_thread()
+			.enable_all()
+			.build()?
+			.block_on(self.execute())
+	}
+
+	async fn execute(&self) -> sc_cli::Result<()> {
+		let call = self.call.to_call()?;
+		let signer = self
+			.suri
+			.as_ref()
+			.map(|suri| sr25519::Pair::from_string(suri, None))
+			.transpose()
+			.map_err(|e| format!("Invalid --suri: {:?}", e))?;
+		let account = match (&signer, &self.from) {
+			(Some(signer), None) => AccountId::from(signer.public()),
+			(None, Some(from)) => from.clone(),
+			_ => return Err("Exactly one of --suri and --from is required".into()),
+		};
+		let signed = signer.is_some() || self.signature.is_some();
+
+		let offline = self.nonce.is_some() &&
+			self.genesis_hash.is_some() &&
+			self.spec_version.is_some() &&
+			self.transaction_version.is_some();
+		let node = if !offline || (signed && !self.print) {
+			Some(WsClientBuilder::default().build(&self.url).await.map_err(rpc_error)?)
+		} else {
+			None
+		};
+
+		let nonce = match self.nonce {
+			Some(nonce) => nonce,
+			None =>
+				request(&node, "system_accountNextIndex", rpc_params![account.to_ss58check()])
+					.await?,
+		};
+		let genesis_hash = match self.genesis_hash {
+			Some(hash) => hash,
+			None => request::<Option<Hash>>(&node, "chain_getBlockHash", rpc_params![0])
+				.await?
+				.ok_or("The node has no genesis block")?,
+		};
+		let (spec_version, transaction_version) =
+			match (self.spec_version, self.transaction_version) {
+				(Some(spec_version), Some(transaction_version)) =>
+					(spec_version, transaction_version),
+				(spec_version, transaction_version) => {
+					let version: RuntimeVersion =
+						request(&node, "state_getRuntimeVersion", rpc_params![]).await?;
+					(
+						spec_version.unwrap_or(version.spec_version),
+						transaction_version.unwrap_or(version.transaction_version),
+					)
+				},
+			};
+
+		let extra: SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(Era::Immortal),
+			frame_system::CheckNonce::<Runtime>::from(nonce),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(self.tip),
+		);
+		let payload = SignedPayload::from_raw(
+			call.clone(),
+			extra.clone(),
+			((), spec_version, transaction_version, genesis_hash, genesis_hash, (), (), ()),
+		);
+
+		let signature = match (&signer, &self.signature) {
+			(Some(signer), _) => payload.using_encoded(|payload| signer.sign(payload)),
+			(None, Some(signature)) => {
+				let signature = sp_core::bytes::from_hex(signature)
+					.map_err(|e| format!("Invalid --signature: {:?}", e))?;
+				sr25519::Signature::from_slice(&signature).ok_or("Invalid --signature length")?
+			},
+			(None, None) => {
+				// payloads longer than 256 bytes are signed hashed, which never happens here
+				println!("0x{}", HexDisplay::from(&payload.encode()));
+				return Ok(())
+			},
+		};
+
+		let extrinsic = UncheckedExtrinsic::new_signed(
+			call,
+			account.into(),
+			Signature::Sr25519(signature),
+			extra,
+		);
+
+		if self.print {
+			println!("0x{}", HexDisplay::from(&extrinsic.encode()));
+		} else {
+			let hash: Hash =
+				request(&node, "author_submitExtrinsic", rpc_params![Bytes(extrinsic.encode())])
+					.await?;
+			println!("{:?}", hash);
+		}
+
+		Ok(())
+	}
+}
+
+/// Parses an amount of tokens with up to `Decimals` decimals (e.g. `1.5`) into the smallest units
+pub fn parse_amount(amount: &str) -> Result<U256, String> {
+	let decimals = Decimals::get() as usize;
+	let (integer, fraction) = amount.split_once('.').unwrap_or((amount, ""));
+
+	if integer.is_empty() && fraction.is_empty() {
+		return Err(format!("Invalid amount `{}`", amount))
+	}
+	if fraction.len() > decimals {
+		return Err(format!("Amount `{}` has more than {} decimals", amount, decimals))
+	}
+
+	U256::from_dec_str(&format!("{}{:0<width$}", integer, fraction, width = decimals))
+		.map_err(|e| format!("Invalid amount `{}`: {:?}", amount, e))
+}
+
+async fn request<T: DeserializeOwned>(
+	node: &Option<WsClient>,
+	method: &str,
+	params: jsonrpsee::core::params::ArrayParams,
+) -> sc_cli::Result<T> {
+	node.as_ref()
+		.expect("the node is connected whenever something is missing or submitted; qed")
+		.request(method, params)
+		.await
+		.map_err(rpc_error)
+}
+
+fn rpc_error(err: impl std::fmt::Display) -> sc_cli::Error {
+	format!("RPC error: {}", err).into()
+}
@@ -0,0 +1,47 @@
+//! `generate-spec` subcommand, to generate the staging chain specification.
+
+use std::{fs, path::PathBuf};
+
+use crate::{
+	chain_spec::{self, StagingConfig},
+	erc20_genesis,
+};
+
+/// The `generate-spec` command used to generate the staging chain specification.
+///
+/// Both the human-readable spec (`<id>.json`) and the raw spec (`<id>-raw.json`), the one nodes
+/// should be started with, are written.
+#[derive(Debug, clap::Parser)]
+pub struct GenerateSpecCmd {
+	/// JSON file of the chain configuration: name, id, authorities, sudo, endowed accounts and
+	/// bootnodes.
+	#[clap(long, value_name = "FILE")]
+	pub config: PathBuf,
+
+	/// CSV or JSON file of the ERC20 genesis allocations.
+	#[clap(long, value_name = "FILE")]
+	pub erc20_allocations: PathBuf,
+
+	/// Directory in which the specs are written.
+	#[clap(long, value_name = "DIR", default_value = ".")]
+	pub out_dir: PathBuf,
+}
+
+impl GenerateSpecCmd {
+	/// Run the command
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let config = StagingConfig::from_json_file(&self.config)?;
+		let id = config.id.clone();
+		let allocations = erc20_genesis::load_allocations(&self.erc20_allocations)?;
+		let spec = chain_spec::staging_config(config, allocations)?;
+
+		fs::create_dir_all(&self.out_dir)?;
+		for (raw, file) in [(false, format!("{}.json", id)), (true, format!("{}-raw.json", id))] {
+			let path = self.out_dir.join(file);
+			fs::write(&path, sc_service::chain_ops::build_spec(&spec, raw)?)?;
+			println!("{}", path.display());
+		}
+
+		Ok(())
+	}
+}
@@ -0,0 +1,268 @@
+//! Index of the erc20 transfers by account.
+//!
+//! The indexer follows the finalized chain and stores every transfer, mint and burn in a local
+//! RocksDB database, under the accounts it involves. The last indexed block is recorded along with
+//! the keys written for it, so blocks that are no longer part of the chain (e.g. after a revert or
+//! a warp sync) are unwound before indexing resumes.
+
+use std::{path::Path, sync::Arc};
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use kvdb::KeyValueDB;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use log::{info, warn};
+use node_template_runtime::{opaque::Block, AccountId, BlockNumber, Hash};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use serde::Serialize;
+use sp_blockchain::{Error as BlockChainError, HeaderBackend, Result as BlockChainResult};
+use sp_core::U256;
+use sp_runtime::traits::Header as _;
+
+use crate::rpc::erc20::{block_events, TransferNotification};
+
+/// Indexer metadata, i.e. the number of the last indexed block
+const COL_META: u32 = 0;
+/// Hash of every indexed block along with the keys written for it
+const COL_BLOCKS: u32 = 1;
+/// Transfers by account, keyed by account, block number and index in the block
+const COL_TRANSFERS: u32 = 2;
+const NUM_COLUMNS: u32 = 3;
+
+const LAST_INDEXED: &[u8] = b"last_indexed";
+
+/// Options of the indexer
+#[derive(Clone, Debug)]
+pub struct IndexerConfig {
+	/// Drop the existing index and index the chain again from block 0
+	pub rebuild: bool,
+}
+
+/// Transfer (or mint or burn) stored in the index
+#[derive(Clone, Debug, PartialEq, Encode, Decode, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexedTransfer {
+	/// Block in which the tokens were moved
+	pub block_number: BlockNumber,
+	/// Hash of the block
+	pub block_hash: Hash,
+	/// Index of the transfer among the erc20 transfers of the block
+	pub index: u32,
+	/// Account from which the tokens were moved, `None` for mints
+	pub from: Option<AccountId>,
+	/// Account to which the tokens were moved, `None` for burns
+	pub to: Option<AccountId>,
+	/// Amount of moved tokens
+	pub amount: U256,
+}
+
+/// Indexed block, recorded so it can be unwound
+#[derive(Encode, Decode)]
+struct BlockRecord {
+	hash: Hash,
+	keys: Vec<Vec<u8>>,
+}
+
+/// Index of the erc20 transfers by account.
+pub struct TransferIndexer {
+	db: Arc<dyn KeyValueDB>,
+}
+
+impl TransferIndexer {
+	/// Opens (or creates) the index at `path`.
+	pub fn open(path: &Path, config: &IndexerConfig) -> BlockChainResult<Self> {
+		let path = path.to_str().ok_or_else(|| db_error("invalid path"))?;
+		let db =
+			Database::open(&DatabaseConfig::with_columns(NUM_COLUMNS), path).map_err(db_error)?;
+		let indexer = Self { db: Arc::new(db) };
+
+		if config.rebuild {
+			info!("💸 Dropping the ERC20 transfers index, it will be rebuilt from block 0");
+			let mut tx = indexer.db.transaction();
+			for col in 0..NUM_COLUMNS {
+				tx.delete_prefix(col, &[]);
+			}
+			indexer.db.write(tx).map_err(db_error)?;
+		}
+
+		Ok(indexer)
+	}
+
+	/// Follows the finalized chain, until the client shuts down.
+	pub async fn run<C, B>(self: Arc<Self>, client: Arc<C>)
+	where
+		B: Backend<Block>,
+		C: HeaderBackend<Block> + BlockchainEvents<Block> + StorageProvider<Block, B>,
+	{
+		// subscribe first, so that no block finalized while catching up is missed
+		let mut finality_notifications = client.finality_notification_stream();
+
+		if let Err(e) = self.sync(&*client, client.info().finalized_number) {
+			warn!("💸 Unable to index ERC20 transfers: {:?}", e);
+		}
+
+		while let Some(notification) = finality_notifications.next().await {
+			if let Err(e) = self.sync(&*client, *notification.header.number()) {
+				warn!("💸 Unable to index ERC20 transfers: {:?}", e);
+			}
+		}
+	}
+
+	/// Transfers involving `account` from block `from_block` on, oldest first.
+	///
+	/// A page never ends in the middle of a block, so it may hold more than `limit` transfers when
+	/// a block has more. The number of the block following the page is returned, if any.
+	pub fn transfers_by_account(
+		&self,
+		account: &AccountId,
+		from_block: BlockNumber,
+		limit: usize,
+	) -> BlockChainResult<(Vec<IndexedTransfer>, Option<BlockNumber>)> {
+		let prefix = account.encode();
+		let start = [&prefix[..], &from_block.to_be_bytes()].concat();
+		let mut transfers = Vec::<IndexedTransfer>::new();
+
+		for seek in seek_prefixes(prefix.len(), start) {
+			for (_, value) in self.db.iter_with_prefix(COL_TRANSFERS, &seek) {
+				let transfer = IndexedTransfer::decode(&mut &value[..]).map_err(db_error)?;
+				if transfers.len() >= limit &&
+					transfers
+						.last()
+						.map_or(false, |last| last.block_number != transfer.block_number)
+				{
+					return Ok((transfers, Some(transfer.block_number)))
+				}
+				transfers.push(transfer);
+			}
+		}
+
+		Ok((transfers, None))
+	}
+
+	/// Indexes the blocks up to `target`, after unwinding the ones that left the chain
+	fn sync<C, B>(&self, client: &C, target: BlockNumber) -> BlockChainResult<()>
+	where
+		B: Backend<Block>,
+		C: HeaderBackend<Block> + StorageProvider<Block, B>,
+	{
+		let mut last = self.last_indexed()?;
+
+		while let Some(number) = last {
+			let record = self.block_record(number)?;
+			if client.hash(number)? == Some(record.hash) {
+				break
+			}
+			info!("💸 Unwinding ERC20 transfers of block #{} ({})", number, record.hash);
+			self.unwind(number, record)?;
+			last = number.checked_sub(1);
+		}
+
+		let first = last.map_or(0, |number| number + 1);
+		if target > first {
+			info!("💸 Indexing ERC20 transfers of blocks #{} to #{}", first, target);
+		}
+		for number in first..=target {
+			let hash = client
+				.hash(number)?
+				.ok_or_else(|| BlockChainError::UnknownBlock(format!("#{}", number)))?;
+			self.index(client, number, hash)?;
+		}
+
+		Ok(())
+	}
+
+	fn index<C, B>(&self, client: &C, number: BlockNumber, hash: Hash) -> BlockChainResult<()>
+	where
+		B: Backend<Block>,
+		C: StorageProvider<Block, B>,
+	{
+		let mut tx = self.db.transaction();
+		let mut keys = vec![];
+
+		// fails rather than recording the block without its transfers, so it's indexed again
+		let transfers = block_events(client, hash)?
+			.into_iter()
+			.filter_map(|event| TransferNotification::from_event(hash, event));
+		for (index, transfer) in transfers.enumerate() {
+			let transfer = IndexedTransfer {
+				block_number: number,
+				block_hash: hash,
+				index: index as u32,
+				from: transfer.from,
+				to: transfer.to,
+				amount: transfer.amount,
+			};
+
+			let mut accounts = transfer.from.iter().chain(transfer.to.iter()).collect::<Vec<_>>();
+			accounts.dedup();
+			for account in accounts {
+				let key = transfer_key(account, number, transfer.index);
+				tx.put(COL_TRANSFERS, &key, &transfer.encode());
+				keys.push(key);
+			}
+		}
+
+		tx.put(COL_BLOCKS, &number.to_be_bytes(), &BlockRecord { hash, keys }.encode());
+		tx.put(COL_META, LAST_INDEXED, &number.encode());
+		self.db.write(tx).map_err(db_error)
+	}
+
+	fn unwind(&self, number: BlockNumber, record: BlockRecord) -> BlockChainResult<()> {
+		let mut tx = self.db.transaction();
+
+		for key in record.keys {
+			tx.delete(COL_TRANSFERS, &key);
+		}
+		tx.delete(COL_BLOCKS, &number.to_be_bytes());
+		match number.checked_sub(1) {
+			Some(previous) => tx.put(COL_META, LAST_INDEXED, &previous.encode()),
+			None => tx.delete(COL_META, LAST_INDEXED),
+		}
+
+		self.db.write(tx).map_err(db_error)
+	}
+
+	fn last_indexed(&self) -> BlockChainResult<Option<BlockNumber>> {
+		self.db
+			.get(COL_META, LAST_INDEXED)
+			.map_err(db_error)?
+			.map(|value| BlockNumber::decode(&mut &value[..]).map_err(db_error))
+			.transpose()
+	}
+
+	fn block_record(&self, number: BlockNumber) -> BlockChainResult<BlockRecord> {
+		let value = self
+			.db
+			.get(COL_BLOCKS, &number.to_be_bytes())
+			.map_err(db_error)?
+			.ok_or_else(|| db_error(format!("missing record of block #{}", number)))?;
+
+		BlockRecord::decode(&mut &value[..]).map_err(db_error)
+	}
+}
+
+/// Key of a transfer in `COL_TRANSFERS`, big endian numbers keep the transfers of an account
+/// ordered by block
+fn transfer_key(account: &AccountId, number: BlockNumber, index: u32) -> Vec<u8> {
+	[&account.encode()[..], &number.to_be_bytes(), &index.to_be_bytes()].concat()
+}
+
+/// Prefixes covering, in order, the keys from `start` on which start with its first `prefix_len`
+/// bytes, since kvdb can only iterate over a prefix: `start` itself, then the ones following it at
+/// every byte position after `prefix_len`, from the last one (e.g. `[1, 2, 3]`, `[1, 2, 4]` to
+/// `[1, 2, 255]`, then `[1, 3]` to `[1, 255]` for `start = [1, 2, 3]` and `prefix_len = 1`).
+fn seek_prefixes(prefix_len: usize, start: Vec<u8>) -> impl Iterator<Item = Vec<u8>> {
+	let following = (prefix_len..start.len()).rev().flat_map({
+		let start = start.clone();
+		move |i| {
+			let start = start.clone();
+			(start[i]..=u8::MAX).skip(1).map(move |byte| [&start[..i], &[byte]].concat())
+		}
+	});
+
+	std::iter::once(start).chain(following)
+}
+
+fn db_error(err: impl std::fmt::Debug) -> BlockChainError {
+	BlockChainError::Backend(format!("ERC20 transfers index: {:?}", err))
+}
@@ -1,3 +1,7 @@
 pub mod chain_spec;
+pub mod erc20_genesis;
+pub mod erc20_storage;
+pub mod erc20_tx;
+pub mod indexer;
 pub mod rpc;
 pub mod service;
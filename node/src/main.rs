@@ -6,6 +6,12 @@ mod chain_spec;
 mod service;
 mod cli;
 mod command;
+mod erc20_cmd;
+mod erc20_genesis;
+mod erc20_storage;
+mod erc20_tx;
+mod generate_spec;
+mod indexer;
 mod rpc;
 
 fn main() -> sc_cli::Result<()> {
@@ -5,9 +5,16 @@
 
 #![warn(missing_docs)]
 
+pub mod bridge;
+pub mod erc20;
+
 use std::sync::Arc;
 
+use crate::indexer::TransferIndexer;
+use bridge::{BridgeConfig, RpcBridge};
 use node_template_runtime::{opaque::Block, AccountId, Balance, Index};
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc::SubscriptionTaskExecutor;
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -22,33 +29,51 @@ pub struct FullDeps<C, P> {
 	pub pool: Arc<P>,
 	/// Whether to deny unsafe calls
 	pub deny_unsafe: DenyUnsafe,
+	/// Executor to drive the subscriptions.
+	pub subscription_executor: SubscriptionTaskExecutor,
+	/// ERC20 transfers index, if enabled.
+	pub erc20_indexer: Option<Arc<TransferIndexer>>,
+	/// Access rules of the ERC20 methods on the public endpoints.
+	pub erc20_rpc: BridgeConfig,
 }
 
 /// Instantiate all full RPC extensions.
-pub fn create_full<C, P>(deps: FullDeps<C, P>) -> jsonrpc_core::IoHandler<sc_rpc::Metadata>
+pub fn create_full<C, P, B>(
+	deps: FullDeps<C, P>,
+) -> Result<jsonrpc_core::IoHandler<sc_rpc::Metadata>, String>
 where
 	C: ProvideRuntimeApi<Block>,
 	C: HeaderBackend<Block> + HeaderMetadata<Block, Error = BlockChainError> + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, B>,
 	C: Send + Sync + 'static,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_erc20_runtime_api::Erc20Api<Block, AccountId, erc20::Erc20Event>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
+	B: Backend<Block> + Send + Sync + 'static,
 {
+	use erc20::{Erc20, Erc20ApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
 	use substrate_frame_rpc_system::{FullSystem, SystemApi};
 
 	let mut io = jsonrpc_core::IoHandler::default();
-	let FullDeps { client, pool, deny_unsafe } = deps;
+	let FullDeps { client, pool, deny_unsafe, subscription_executor, erc20_indexer, erc20_rpc } =
+		deps;
 
 	io.extend_with(SystemApi::to_delegate(FullSystem::new(client.clone(), pool, deny_unsafe)));
 
 	io.extend_with(TransactionPaymentApi::to_delegate(TransactionPayment::new(client.clone())));
 
+	// the ERC20 methods make up a jsonrpsee module, bridged to the jsonrpc-core endpoints
+	let erc20_module = Erc20::<_, B>::new(client.clone(), erc20_indexer, deny_unsafe).into_rpc();
+	let erc20_bridge = RpcBridge::new(erc20_module, erc20::SUBSCRIPTIONS, erc20_rpc)?;
+	io.extend_with(erc20_bridge.io_handler(deny_unsafe, subscription_executor));
+
 	// Extend this RPC with a custom API by using the following syntax.
 	// `YourRpcStruct` should have a reference to a client, which is needed
 	// to call into the runtime.
 	// `io.extend_with(YourRpcTrait::to_delegate(YourRpcStruct::new(ReferenceToClient, ...)));`
 
-	io
+	Ok(io)
 }
@@ -0,0 +1,328 @@
+//! Serves the methods of a jsonrpsee module on the RPC endpoints of the node.
+//!
+//! The RPC servers of this Substrate release are built on jsonrpc-core, so every method of the
+//! module is registered in the `IoHandler` of `create_full` and forwarded to the module as a raw
+//! JSON-RPC request, the notifications of its subscriptions being forwarded back the same way.
+//!
+//! The endpoints denying unsafe calls (see `--rpc-methods`) are the public ones: they only serve
+//! the methods of the allow-list (unsubscribing is allowed along with subscribing), and every
+//! connection gets its own rate limit. The calls over HTTP, which have no connection, share one.
+
+use std::{
+	collections::{HashMap, HashSet},
+	fmt::Debug,
+	sync::{Arc, Mutex},
+	time::{Duration, Instant},
+};
+
+use futures::{
+	future::{self, AbortHandle, Abortable},
+	task::SpawnExt,
+};
+use jsonrpc_core::{Error as RpcError, ErrorCode, MetaIoHandler, Output, Params, Value};
+use jsonrpc_pubsub::{PubSubHandler, PubSubMetadata, Session, Subscriber, SubscriptionId};
+use jsonrpsee::Methods;
+use log::warn;
+use sc_rpc::{Metadata, SubscriptionTaskExecutor};
+use sc_rpc_api::DenyUnsafe;
+use serde_json::json;
+
+/// Error code returned when a connection exceeds its rate limit
+const RATE_LIMITED: i64 = -32029;
+/// Number of notifications of a subscription buffered by the module
+const NOTIFICATIONS_BUFFER: usize = 16;
+
+/// Names of the methods and of the notification of a subscription
+#[derive(Clone, Copy, Debug)]
+pub struct SubscriptionNames {
+	/// Method subscribing
+	pub subscribe: &'static str,
+	/// Method of the notifications
+	pub notification: &'static str,
+	/// Method unsubscribing
+	pub unsubscribe: &'static str,
+}
+
+/// Access rules of the public endpoints
+#[derive(Clone, Debug)]
+pub struct BridgeConfig {
+	/// Maximum number of calls per second on a connection, unlimited if 0
+	pub rate_limit: u32,
+	/// Methods to serve, all of them if `None`
+	pub methods: Option<HashSet<String>>,
+}
+
+/// Methods of a jsonrpsee module, served on the endpoints of the node
+#[derive(Clone)]
+pub struct RpcBridge {
+	methods: Methods,
+	subscriptions: &'static [SubscriptionNames],
+	config: BridgeConfig,
+}
+
+impl RpcBridge {
+	/// Bridges the `methods` of a module, whose subscriptions are named by `subscriptions`.
+	/// Fails if the allow-list of `config` holds a method the module doesn't have.
+	pub fn new(
+		methods: impl Into<Methods>,
+		subscriptions: &'static [SubscriptionNames],
+		config: BridgeConfig,
+	) -> Result<Self, String> {
+		let methods = methods.into();
+
+		if let Some(allowed) = &config.methods {
+			let known = methods.method_names().collect::<HashSet<_>>();
+			if let Some(unknown) = allowed.iter().find(|method| !known.contains(method.as_str())) {
+				return Err(format!("Unknown RPC method `{}`", unknown))
+			}
+		}
+
+		Ok(Self { methods, subscriptions, config })
+	}
+
+	/// Handler of the bridged methods on an endpoint, which `IoHandler::extend_with` takes
+	pub fn io_handler(
+		&self,
+		deny_unsafe: DenyUnsafe,
+		executor: SubscriptionTaskExecutor,
+	) -> MetaIoHandler<Metadata> {
+		let public = deny_unsafe.check_if_safe().is_err();
+		let guard = Arc::new(Guard {
+			methods: if public { self.config.methods.clone() } else { None },
+			rate_limit: if public { self.config.rate_limit } else { 0 },
+			windows: Default::default(),
+		});
+		let mut handler = PubSubHandler::new(MetaIoHandler::default());
+
+		for subscription in self.subscriptions {
+			self.add_subscription(&mut handler, *subscription, guard.clone(), executor.clone());
+		}
+		let subscription_methods = self
+			.subscriptions
+			.iter()
+			.flat_map(|names| [names.subscribe, names.unsubscribe])
+			.collect::<HashSet<_>>();
+		for name in self.methods.method_names() {
+			if !subscription_methods.contains(name) {
+				self.add_method(&mut handler, name, guard.clone());
+			}
+		}
+
+		handler.into()
+	}
+
+	fn add_method(
+		&self,
+		handler: &mut PubSubHandler<Metadata>,
+		name: &'static str,
+		guard: Arc<Guard>,
+	) {
+		let methods = self.methods.clone();
+
+		handler.add_method_with_meta(name, move |params: Params, metadata: Metadata| {
+			let (methods, guard) = (methods.clone(), guard.clone());
+
+			async move {
+				guard.check(name, &metadata)?;
+				let (response, _) = methods
+					.raw_json_request(&request(name, params), 1)
+					.await
+					.map_err(internal_error)?;
+
+				output(&response)
+			}
+		});
+	}
+
+	fn add_subscription(
+		&self,
+		handler: &mut PubSubHandler<Metadata>,
+		names: SubscriptionNames,
+		guard: Arc<Guard>,
+		executor: SubscriptionTaskExecutor,
+	) {
+		let methods = self.methods.clone();
+		// forwarding of the notifications of every subscription, by id
+		let forwards = Arc::new(Mutex::new(HashMap::<SubscriptionId, AbortHandle>::new()));
+		let unsubscribe_forwards = forwards.clone();
+
+		let subscribe = move |params: Params, metadata: Metadata, subscriber: Subscriber| {
+			if let Err(e) = guard.check(names.subscribe, &metadata) {
+				let _ = subscriber.reject(e);
+				return
+			}
+
+			let (methods, forwards, session) =
+				(methods.clone(), forwards.clone(), metadata.session());
+			let task = async move {
+				let subscribed = methods
+					.raw_json_request(&request(names.subscribe, params), NOTIFICATIONS_BUFFER);
+				let (response, mut notifications) = match subscribed.await {
+					Ok(subscribed) => subscribed,
+					Err(e) => {
+						let _ = subscriber.reject(internal_error(e));
+						return
+					},
+				};
+				// the subscription keeps the id given by the module, which its notifications hold
+				let id = match output(&response).map(|id| SubscriptionId::parse_value(&id)) {
+					Ok(Some(id)) => id,
+					Ok(None) => {
+						let _ = subscriber.reject(internal_error(response));
+						return
+					},
+					Err(e) => {
+						let _ = subscriber.reject(e);
+						return
+					},
+				};
+				let sink = match subscriber.assign_id(id.clone()) {
+					Ok(sink) => sink,
+					// the subscriber is gone
+					Err(()) => return,
+				};
+
+				let (abort, registration) = AbortHandle::new_pair();
+				lock(&forwards).insert(id.clone(), abort.clone());
+				if let Some(session) = session {
+					session.on_drop(move || abort.abort());
+				}
+
+				let forward = async move {
+					while let Some(notification) = notifications.recv().await {
+						let params = match notification_params(&notification) {
+							Some(params) => params,
+							None => {
+								warn!(
+									"Invalid notification of {}: {}",
+									names.subscribe, notification
+								);
+								continue
+							},
+						};
+						if sink.notify(params).is_err() {
+							break
+						}
+					}
+				};
+				// dropping the notifications ends the subscription of the module
+				let _ = Abortable::new(forward, registration).await;
+				lock(&forwards).remove(&id);
+			};
+
+			if let Err(e) = executor.spawn(task) {
+				warn!("Unable to spawn {} subscription: {:?}", names.subscribe, e);
+			}
+		};
+
+		let unsubscribe = move |id: SubscriptionId, _metadata: Option<Metadata>| {
+			future::ready(match lock(&unsubscribe_forwards).remove(&id) {
+				Some(abort) => {
+					abort.abort();
+					Ok(Value::Bool(true))
+				},
+				None => Err(RpcError::invalid_params("Invalid subscription id.")),
+			})
+		};
+
+		handler.add_subscription(
+			names.notification,
+			(names.subscribe, subscribe),
+			(names.unsubscribe, unsubscribe),
+		);
+	}
+}
+
+/// Access checks of an endpoint
+struct Guard {
+	/// Methods served, all of them if `None`
+	methods: Option<HashSet<String>>,
+	/// Maximum number of calls per second on a connection, unlimited if 0
+	rate_limit: u32,
+	/// Start of the current one second window of every connection and the number of calls in it,
+	/// by session (0 for the calls without a session)
+	windows: Arc<Mutex<HashMap<usize, (Instant, u32)>>>,
+}
+
+impl Guard {
+	/// Fails if `method` isn't served or the connection of `metadata` exceeds its rate limit
+	fn check(&self, method: &str, metadata: &Metadata) -> Result<(), RpcError> {
+		if self.methods.as_ref().map_or(false, |methods| !methods.contains(method)) {
+			return Err(RpcError::method_not_found())
+		}
+		if self.rate_limit > 0 && !self.take_call(metadata.session()) {
+			return Err(RpcError {
+				code: ErrorCode::ServerError(RATE_LIMITED),
+				message: "Too many requests.".into(),
+				data: None,
+			})
+		}
+
+		Ok(())
+	}
+
+	/// Counts a call in the current window of the connection of `session`, `false` if the window
+	/// is full
+	fn take_call(&self, session: Option<Arc<Session>>) -> bool {
+		let key = session.as_ref().map_or(0, |session| Arc::as_ptr(session) as usize);
+		let mut windows = lock(&self.windows);
+		let now = Instant::now();
+
+		let window = windows.entry(key).or_insert_with(|| {
+			// the window is dropped along with the connection
+			if let Some(session) = &session {
+				let windows = self.windows.clone();
+				session.on_drop(move || {
+					lock(&windows).remove(&key);
+				});
+			}
+			(now, 0)
+		});
+		if now.duration_since(window.0) >= Duration::from_secs(1) {
+			*window = (now, 0);
+		}
+		if window.1 >= self.rate_limit {
+			return false
+		}
+		window.1 += 1;
+
+		true
+	}
+}
+
+/// JSON-RPC request calling `method` of the module with `params`
+fn request(method: &str, params: Params) -> String {
+	let params = match params {
+		Params::None => json!([]),
+		params => json!(params),
+	};
+
+	json!({ "jsonrpc": "2.0", "id": 0, "method": method, "params": params }).to_string()
+}
+
+/// Result of a JSON-RPC response of the module
+fn output(response: &str) -> Result<Value, RpcError> {
+	match serde_json::from_str::<Output>(response).map_err(internal_error)? {
+		Output::Success(success) => Ok(success.result),
+		Output::Failure(failure) => Err(failure.error),
+	}
+}
+
+/// Params of a notification of the module, holding the subscription id and the result
+fn notification_params(notification: &str) -> Option<Params> {
+	let mut notification = serde_json::from_str::<Value>(notification).ok()?;
+
+	serde_json::from_value(notification.get_mut("params")?.take()).ok()
+}
+
+fn internal_error(err: impl Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::InternalError,
+		message: "Unable to call the RPC module.".into(),
+		data: Some(format!("{:?}", err).into()),
+	}
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+	mutex.lock().expect("the lock is never poisoned; qed")
+}
@@ -0,0 +1,693 @@
+//! RPC methods specific to the erc20 pallet.
+//!
+//! The methods make up a jsonrpsee module, served on the RPC endpoints of the node through
+//! `super::bridge`.
+//! Subscriptions are driven by block import notifications: the `pallet_erc20` events of every new
+//! best block are decoded from its state and matched against the subscription parameters.
+//!
+//! `erc20_transfersByAccount` is served from the transfers index, when the node runs it. The
+//! holders are listed by iterating the `Balance` map in the state of the requested block.
+//! Transfers are simulated, and the ABI-encoded calldata of `erc20_ethCall` is executed, by the
+//! runtime on top of the state of the requested block.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::{Decode, Encode};
+use frame_support::traits::PalletInfoAccess;
+use futures::{future, stream, Stream, StreamExt};
+use jsonrpsee::{
+	core::{async_trait, RpcResult, SubscriptionResult},
+	proc_macros::rpc,
+	types::{ErrorObject, ErrorObjectOwned},
+	PendingSubscriptionSink, SubscriptionMessage,
+};
+use log::warn;
+use node_template_runtime::{
+	opaque::Block,
+	pallet_erc20::{self, EthereumAddress},
+	AccountId, BlockNumber, Erc20 as Erc20Pallet, Event, Hash, Runtime,
+};
+use pallet_erc20_runtime_api::Erc20Api as Erc20RuntimeApi;
+use sc_client_api::{Backend, BlockchainEvents, StorageProvider};
+use sc_rpc_api::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{Error as BlockChainError, HeaderBackend, Result as BlockChainResult};
+use sp_core::{storage::StorageKey, twox_128, Bytes, U256};
+use sp_runtime::{generic::BlockId, DispatchError};
+
+use super::bridge::SubscriptionNames;
+use crate::{
+	erc20_storage,
+	indexer::{IndexedTransfer, TransferIndexer},
+};
+
+/// Event of the erc20 pallet, as configured in the runtime
+pub type Erc20Event = pallet_erc20::Event<Runtime>;
+
+/// Error code returned when the runtime could not be queried
+const RUNTIME_ERROR: i32 = 1;
+/// Error code returned when the transfers index is disabled or could not be read
+const INDEX_ERROR: i32 = 2;
+/// Error code returned when the state could not be read
+const STATE_ERROR: i32 = 3;
+/// Error code returned when the calldata of `erc20_ethCall` could not be executed, like the
+/// "execution reverted" error of `eth_call`
+const EXECUTION_ERROR: i32 = 4;
+/// Error code returned when an unsafe method is called on an endpoint denying unsafe calls
+const UNSAFE_ERROR: i32 = 5;
+
+/// Default (and maximum) number of transfers returned by `erc20_transfersByAccount`
+const MAX_TRANSFERS_PAGE: u32 = 1000;
+/// Default (and maximum) number of holders returned by `erc20_holders` and `erc20_topHolders`
+const MAX_HOLDERS_PAGE: u32 = 1000;
+
+/// Subscriptions of the ERC20 RPC module
+pub const SUBSCRIPTIONS: &[SubscriptionNames] = &[
+	SubscriptionNames {
+		subscribe: "erc20_subscribeBalance",
+		notification: "erc20_balance",
+		unsubscribe: "erc20_unsubscribeBalance",
+	},
+	SubscriptionNames {
+		subscribe: "erc20_subscribeAllowance",
+		notification: "erc20_allowance",
+		unsubscribe: "erc20_unsubscribeAllowance",
+	},
+	SubscriptionNames {
+		subscribe: "erc20_subscribeTransfers",
+		notification: "erc20_transfers",
+		unsubscribe: "erc20_unsubscribeTransfers",
+	},
+];
+
+/// Filter of the `erc20_subscribeTransfers` subscription, missing fields match any account
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TransferFilter {
+	/// Account from which the tokens are moved
+	pub from: Option<AccountId>,
+	/// Account to which the tokens are moved
+	pub to: Option<AccountId>,
+}
+
+impl TransferFilter {
+	fn matches(&self, transfer: &TransferNotification) -> bool {
+		(self.from.is_none() || self.from == transfer.from) &&
+			(self.to.is_none() || self.to == transfer.to)
+	}
+}
+
+/// Movement of tokens reported by the `erc20_subscribeTransfers` subscription.
+/// Mints are reported without `from` and burns are reported without `to`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferNotification {
+	/// Block in which the tokens were moved
+	pub block_hash: Hash,
+	/// Account from which the tokens were moved
+	pub from: Option<AccountId>,
+	/// Account to which the tokens were moved
+	pub to: Option<AccountId>,
+	/// Amount of moved tokens
+	pub amount: U256,
+}
+
+impl TransferNotification {
+	pub(crate) fn from_event(block_hash: Hash, event: Erc20Event) -> Option<Self> {
+		let (from, to, amount) = match event {
+			pallet_erc20::Event::Transfer { from, to, amount } => (Some(from), Some(to), amount),
+			pallet_erc20::Event::Mint { account, amount } => (None, Some(account), amount),
+			pallet_erc20::Event::Burn { account, amount } => (Some(account), None, amount),
+			_ => return None,
+		};
+
+		Some(Self { block_hash, from, to, amount })
+	}
+}
+
+/// Page of transfers returned by `erc20_transfersByAccount`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransfersPage {
+	/// Transfers involving the account, oldest first
+	pub transfers: Vec<IndexedTransfer>,
+	/// Block from which the next page starts, `None` on the last page
+	pub next_block: Option<BlockNumber>,
+}
+
+/// Account holding tokens
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Holder {
+	/// Holding account
+	pub account: AccountId,
+	/// Balance of the account
+	pub balance: U256,
+}
+
+/// Holders returned by `erc20_holders` and `erc20_topHolders`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HoldersPage {
+	/// Holders in the page
+	pub holders: Vec<Holder>,
+	/// Cursor of the next page, `None` on the last page (and for `erc20_topHolders`)
+	pub next_cursor: Option<AccountId>,
+	/// Total number of accounts with a non-zero balance, only counted for the first page of
+	/// `erc20_holders` (and for `erc20_topHolders`) as it takes a scan of all the balances
+	pub holder_count: Option<u32>,
+}
+
+/// Event emitted by a simulated transfer
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event")]
+pub enum SimulatedEvent {
+	/// Tokens were moved from `from` to `to`
+	Transfer {
+		/// Account from which the tokens were moved
+		from: AccountId,
+		/// Account to which the tokens were moved
+		to: AccountId,
+		/// Amount of moved tokens
+		amount: U256,
+	},
+	/// Allowance of `spender` over the tokens of `owner` was set
+	Approval {
+		/// Account owning the tokens
+		owner: AccountId,
+		/// Account allowed to spend the tokens
+		spender: AccountId,
+		/// New allowance
+		amount: U256,
+	},
+}
+
+/// Outcome of the transfer simulated by `erc20_simulateTransfer`
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase", tag = "outcome")]
+pub enum SimulatedTransfer {
+	/// The transfer would succeed
+	#[serde(rename_all = "camelCase")]
+	Success {
+		/// Balance of the sender after the transfer
+		from_balance: U256,
+		/// Balance of the recipient after the transfer
+		to_balance: U256,
+		/// Remaining allowance of the spender, if any
+		allowance: Option<U256>,
+		/// Events emitted by the transfer
+		events: Vec<SimulatedEvent>,
+	},
+	/// The transfer would fail
+	Failure {
+		/// Name of the `pallet_erc20::Error` variant, or description of any other error
+		error: String,
+	},
+}
+
+/// Call executed by `erc20_ethCall`, with the fields of an `eth_call` transaction that matter to
+/// the pallet (there is no contract address, the pallet being the only ERC20 token)
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthCallRequest {
+	/// Address making the call, linked to the account sending `transfer`, `approve` or
+	/// `transferFrom`
+	pub from: Option<EthereumAddress>,
+	/// ABI-encoded calldata of an ERC20 function
+	pub data: Bytes,
+}
+
+/// ERC20 RPC methods.
+#[rpc(server, namespace = "erc20")]
+pub trait Erc20Api {
+	/// Subscribes to the balance of `account`.
+	/// The current balance is sent right away, then it's sent every time a new best block
+	/// changes it.
+	#[subscription(
+		name = "subscribeBalance" => "balance",
+		unsubscribe = "unsubscribeBalance",
+		item = U256
+	)]
+	async fn subscribe_balance(&self, account: AccountId) -> SubscriptionResult;
+
+	/// Subscribes to the allowance of `spender` over the tokens of `owner`.
+	/// The current allowance is sent right away, then it's sent every time a new best block
+	/// changes it.
+	#[subscription(
+		name = "subscribeAllowance" => "allowance",
+		unsubscribe = "unsubscribeAllowance",
+		item = U256
+	)]
+	async fn subscribe_allowance(&self, owner: AccountId, spender: AccountId)
+		-> SubscriptionResult;
+
+	/// Subscribes to the transfers (along with mints and burns) matching `filter`
+	/// in the new best blocks.
+	#[subscription(
+		name = "subscribeTransfers" => "transfers",
+		unsubscribe = "unsubscribeTransfers",
+		item = TransferNotification
+	)]
+	async fn subscribe_transfers(&self, filter: Option<TransferFilter>) -> SubscriptionResult;
+
+	/// Returns the finalized transfers (along with mints and burns) involving `account`,
+	/// from block `from_block` (0 by default) on.
+	/// At most `limit` transfers are returned, unless a single block holds more.
+	#[method(name = "transfersByAccount", blocking)]
+	fn transfers_by_account(
+		&self,
+		account: AccountId,
+		from_block: Option<BlockNumber>,
+		limit: Option<u32>,
+	) -> RpcResult<TransfersPage>;
+
+	/// Returns up to `limit` (1000 if missing or 0) accounts with a non-zero balance at block `at`
+	/// (the best block by default), in storage order. The holders following `cursor` are returned,
+	/// the first page is returned if there is no `cursor`. The first page scans all the balances
+	/// to count the holders, the next ones only read up to the end of the page.
+	#[method(name = "holders", blocking)]
+	fn holders(
+		&self,
+		cursor: Option<AccountId>,
+		limit: Option<u32>,
+		at: Option<Hash>,
+	) -> RpcResult<HoldersPage>;
+
+	/// Returns the `n` (at most 1000) accounts with the highest balances at block `at` (the best
+	/// block by default), highest first. Every call reads and sorts all the balances, so it's
+	/// meant for occasional queries (e.g. cached by an explorer) rather than polling, and it's
+	/// unsafe: the endpoints denying unsafe calls reject it.
+	#[method(name = "topHolders", blocking)]
+	fn top_holders(&self, n: u32, at: Option<Hash>) -> RpcResult<HoldersPage>;
+
+	/// Simulates a transfer of `amount` tokens from `from` to `to` on top of block `at` (the best
+	/// block by default), without submitting an extrinsic. The transfer is made by `spender` with
+	/// `transfer_from`, or by `from` itself with `transfer` if there is no `spender`.
+	#[method(name = "simulateTransfer", blocking)]
+	fn simulate_transfer(
+		&self,
+		from: AccountId,
+		to: AccountId,
+		amount: U256,
+		spender: Option<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<SimulatedTransfer>;
+
+	/// Executes the ABI-encoded calldata of an ERC20 function on top of block `at` (the best block
+	/// by default), like `eth_call` on an ERC20 contract, and returns the ABI-encoded result.
+	/// The functions changing the state are dry-run: they return `true` unless they would fail.
+	#[method(name = "ethCall", blocking)]
+	fn eth_call(&self, request: EthCallRequest, at: Option<Hash>) -> RpcResult<Bytes>;
+}
+
+/// Implementation of the ERC20 RPC methods.
+pub struct Erc20<C, B> {
+	client: Arc<C>,
+	indexer: Option<Arc<TransferIndexer>>,
+	deny_unsafe: DenyUnsafe,
+	_backend: PhantomData<B>,
+}
+
+impl<C, B> Erc20<C, B> {
+	/// Creates a new instance of the ERC20 RPC handler.
+	pub fn new(
+		client: Arc<C>,
+		indexer: Option<Arc<TransferIndexer>>,
+		deny_unsafe: DenyUnsafe,
+	) -> Self {
+		Self { client, indexer, deny_unsafe, _backend: PhantomData }
+	}
+}
+
+impl<C, B> Erc20<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: BlockchainEvents<Block> + StorageProvider<Block, B> + Send + Sync + 'static,
+{
+	/// Stream of the new best blocks along with the erc20 events emitted in them, the blocks whose
+	/// events can't be read are skipped
+	fn best_block_events(&self) -> impl Stream<Item = (Hash, Vec<Erc20Event>)> + Send + 'static {
+		let client = self.client.clone();
+
+		self.client
+			.import_notification_stream()
+			.filter(|notification| future::ready(notification.is_new_best))
+			.filter_map(move |notification| {
+				let events = block_events(&*client, notification.hash)
+					.map_err(|e| {
+						warn!("Unable to read events of block {}: {:?}", notification.hash, e)
+					})
+					.ok();
+				future::ready(events.map(|events| (notification.hash, events)))
+			})
+	}
+
+	/// Accounts with a non-zero balance at block `at`
+	fn all_holders(&self, at: &BlockId<Block>) -> RpcResult<Vec<Holder>> {
+		Ok(erc20_storage::holders(&*self.client, at)
+			.map_err(state_error)?
+			.into_iter()
+			.map(|(account, balance)| Holder { account, balance })
+			.collect())
+	}
+}
+
+#[async_trait]
+impl<C, B> Erc20ApiServer for Erc20<C, B>
+where
+	B: Backend<Block> + Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ BlockchainEvents<Block>
+		+ StorageProvider<Block, B>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: Erc20RuntimeApi<Block, AccountId, Erc20Event>,
+{
+	async fn subscribe_balance(
+		&self,
+		pending: PendingSubscriptionSink,
+		account: AccountId,
+	) -> SubscriptionResult {
+		let client = self.client.clone();
+		let initial = match balance_at(&*client, client.info().best_hash, account.clone()) {
+			Ok(balance) => balance,
+			Err(e) => {
+				pending.reject(e).await;
+				return Ok(())
+			},
+		};
+
+		let updates = self.best_block_events().filter_map(move |(hash, events)| {
+			let touched = events.iter().any(|event| match event {
+				pallet_erc20::Event::Transfer { from, to, .. } =>
+					*from == account || *to == account,
+				pallet_erc20::Event::Mint { account: minted, .. } => *minted == account,
+				pallet_erc20::Event::Burn { account: burnt, .. } => *burnt == account,
+				_ => false,
+			});
+
+			future::ready(
+				touched.then(|| balance_at(&*client, hash, account.clone())).and_then(log_error),
+			)
+		});
+
+		pipe_from_stream(pending, stream::once(future::ready(initial)).chain(updates)).await
+	}
+
+	async fn subscribe_allowance(
+		&self,
+		pending: PendingSubscriptionSink,
+		owner: AccountId,
+		spender: AccountId,
+	) -> SubscriptionResult {
+		let client = self.client.clone();
+		let initial =
+			match allowance_at(&*client, client.info().best_hash, owner.clone(), spender.clone()) {
+				Ok(allowance) => allowance,
+				Err(e) => {
+					pending.reject(e).await;
+					return Ok(())
+				},
+			};
+
+		let updates = self.best_block_events().filter_map(move |(hash, events)| {
+			let touched = events.iter().any(|event| match event {
+				pallet_erc20::Event::Approval { owner: o, spender: s, .. } =>
+					*o == owner && *s == spender,
+				_ => false,
+			});
+
+			future::ready(
+				touched
+					.then(|| allowance_at(&*client, hash, owner.clone(), spender.clone()))
+					.and_then(log_error),
+			)
+		});
+
+		pipe_from_stream(pending, stream::once(future::ready(initial)).chain(updates)).await
+	}
+
+	async fn subscribe_transfers(
+		&self,
+		pending: PendingSubscriptionSink,
+		filter: Option<TransferFilter>,
+	) -> SubscriptionResult {
+		let filter = filter.unwrap_or_default();
+
+		let transfers = self.best_block_events().flat_map(move |(hash, events)| {
+			let transfers = events
+				.into_iter()
+				.filter_map(|event| TransferNotification::from_event(hash, event))
+				.filter(|transfer| filter.matches(transfer))
+				.collect::<Vec<_>>();
+
+			stream::iter(transfers)
+		});
+
+		pipe_from_stream(pending, transfers).await
+	}
+
+	fn transfers_by_account(
+		&self,
+		account: AccountId,
+		from_block: Option<BlockNumber>,
+		limit: Option<u32>,
+	) -> RpcResult<TransfersPage> {
+		let indexer = self.indexer.as_ref().ok_or_else(|| {
+			ErrorObject::owned(
+				INDEX_ERROR,
+				"The ERC20 transfers index is disabled, see `--enable-erc20-indexer`.",
+				None::<()>,
+			)
+		})?;
+		let limit = limit.unwrap_or(MAX_TRANSFERS_PAGE).min(MAX_TRANSFERS_PAGE);
+
+		let (transfers, next_block) = indexer
+			.transfers_by_account(&account, from_block.unwrap_or_default(), limit as usize)
+			.map_err(|e| {
+				ErrorObject::owned(
+					INDEX_ERROR,
+					"Unable to read the ERC20 transfers index.",
+					Some(format!("{:?}", e)),
+				)
+			})?;
+
+		Ok(TransfersPage { transfers, next_block })
+	}
+
+	fn holders(
+		&self,
+		cursor: Option<AccountId>,
+		limit: Option<u32>,
+		at: Option<Hash>,
+	) -> RpcResult<HoldersPage> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		// an empty page would return the `cursor` it was given as its `next_cursor`
+		let limit = limit
+			.filter(|limit| *limit > 0)
+			.unwrap_or(MAX_HOLDERS_PAGE)
+			.min(MAX_HOLDERS_PAGE) as usize;
+		let prefix = erc20_storage::balance_prefix();
+		// the holders are counted while reading the first page, the next pages stop at their end
+		let count_holders = cursor.is_none();
+		let start = cursor.map(|account| erc20_storage::balance_key(&account));
+
+		let keys = self
+			.client
+			.storage_keys_iter(&at, Some(&prefix), start.as_ref())
+			.map_err(state_error)?;
+		let mut holders = vec![];
+		let mut next_cursor = None;
+		let mut holder_count = 0;
+
+		for key in keys {
+			let balance =
+				erc20_storage::read_u256(&*self.client, &at, &key).map_err(state_error)?;
+			if balance.is_zero() {
+				continue
+			}
+			holder_count += 1;
+			if holders.len() < limit {
+				let account = erc20_storage::balance_account(&key)
+					.ok_or_else(|| state_error(format!("invalid balance key {:?}", key)))?;
+				holders.push(Holder { account, balance });
+				continue
+			}
+			if next_cursor.is_none() {
+				next_cursor = holders.last().map(|holder: &Holder| holder.account.clone());
+			}
+			if !count_holders {
+				break
+			}
+		}
+
+		Ok(HoldersPage { holders, next_cursor, holder_count: count_holders.then(|| holder_count) })
+	}
+
+	fn top_holders(&self, n: u32, at: Option<Hash>) -> RpcResult<HoldersPage> {
+		self.deny_unsafe
+			.check_if_safe()
+			.map_err(|e| ErrorObject::owned(UNSAFE_ERROR, e.to_string(), None::<()>))?;
+
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let n = n.min(MAX_HOLDERS_PAGE) as usize;
+		let mut holders = self.all_holders(&at)?;
+		let holder_count = holders.len() as u32;
+
+		holders.sort_by(|a, b| b.balance.cmp(&a.balance));
+		holders.truncate(n);
+
+		Ok(HoldersPage { holders, next_cursor: None, holder_count: Some(holder_count) })
+	}
+
+	fn simulate_transfer(
+		&self,
+		from: AccountId,
+		to: AccountId,
+		amount: U256,
+		spender: Option<AccountId>,
+		at: Option<Hash>,
+	) -> RpcResult<SimulatedTransfer> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let simulation = self
+			.client
+			.runtime_api()
+			.simulate_transfer(&at, from, to, amount, spender)
+			.map_err(runtime_error)?;
+
+		Ok(match simulation {
+			Ok(simulation) => SimulatedTransfer::Success {
+				from_balance: simulation.from_balance,
+				to_balance: simulation.to_balance,
+				allowance: simulation.allowance,
+				events: simulation
+					.events
+					.into_iter()
+					.filter_map(|event| match event {
+						pallet_erc20::Event::Transfer { from, to, amount } =>
+							Some(SimulatedEvent::Transfer { from, to, amount }),
+						pallet_erc20::Event::Approval { owner, spender, amount } =>
+							Some(SimulatedEvent::Approval { owner, spender, amount }),
+						_ => None,
+					})
+					.collect(),
+			},
+			Err(error) => SimulatedTransfer::Failure { error: describe_error(error) },
+		})
+	}
+
+	fn eth_call(&self, request: EthCallRequest, at: Option<Hash>) -> RpcResult<Bytes> {
+		let at = BlockId::Hash(at.unwrap_or_else(|| self.client.info().best_hash));
+		let result = self
+			.client
+			.runtime_api()
+			.eth_call(&at, request.from, request.data.0)
+			.map_err(runtime_error)?;
+
+		result.map(Bytes).map_err(|error| {
+			ErrorObject::owned(EXECUTION_ERROR, "Execution reverted.", Some(describe_error(error)))
+		})
+	}
+}
+
+/// Decodes the erc20 events emitted in the block `hash` from its state, fails if the state of
+/// the block can't be read (e.g. it was pruned) or its events can't be decoded
+pub(crate) fn block_events<C, B>(client: &C, hash: Hash) -> BlockChainResult<Vec<Erc20Event>>
+where
+	B: Backend<Block>,
+	C: StorageProvider<Block, B>,
+{
+	// `frame_system::Events` storage is private, so the key is built by hand
+	let key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+
+	let records = match client.storage(&BlockId::Hash(hash), &key)? {
+		Some(data) => Vec::<frame_system::EventRecord<Event, Hash>>::decode(&mut &data.0[..])
+			.map_err(|e| {
+				BlockChainError::Backend(format!("invalid events of block {}: {:?}", hash, e))
+			})?,
+		None => vec![],
+	};
+
+	Ok(records
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::Erc20(event) => Some(event),
+			_ => None,
+		})
+		.collect())
+}
+
+fn balance_at<C>(client: &C, hash: Hash, account: AccountId) -> RpcResult<U256>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Erc20RuntimeApi<Block, AccountId, Erc20Event>,
+{
+	client
+		.runtime_api()
+		.balance_of(&BlockId::Hash(hash), account)
+		.map_err(runtime_error)
+}
+
+fn allowance_at<C>(client: &C, hash: Hash, owner: AccountId, spender: AccountId) -> RpcResult<U256>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: Erc20RuntimeApi<Block, AccountId, Erc20Event>,
+{
+	client
+		.runtime_api()
+		.allowance(&BlockId::Hash(hash), owner, spender)
+		.map_err(runtime_error)
+}
+
+/// Name of the erc20 pallet error, or debug representation of any other dispatch error
+fn describe_error(error: DispatchError) -> String {
+	if let DispatchError::Module(module_error) = &error {
+		if module_error.index as usize == Erc20Pallet::index() {
+			// the first byte of the module error is the index of the variant
+			let erc20_error =
+				pallet_erc20::Error::<Runtime>::decode(&mut &module_error.error.encode()[..]);
+			if let Ok(erc20_error) = erc20_error {
+				return format!("{:?}", erc20_error)
+			}
+		}
+	}
+
+	format!("{:?}", error)
+}
+
+fn state_error(err: impl std::fmt::Debug) -> ErrorObjectOwned {
+	ErrorObject::owned(STATE_ERROR, "Unable to read ERC20 state.", Some(format!("{:?}", err)))
+}
+
+fn runtime_error(err: impl std::fmt::Debug) -> ErrorObjectOwned {
+	ErrorObject::owned(RUNTIME_ERROR, "Unable to query ERC20 state.", Some(format!("{:?}", err)))
+}
+
+/// Logs the error of a subscription update, which is then skipped
+fn log_error<T>(result: RpcResult<T>) -> Option<T> {
+	result.map_err(|e| warn!("Unable to query ERC20 state: {:?}", e)).ok()
+}
+
+/// Accepts the subscription and sends it the `items`, until the subscriber is gone
+async fn pipe_from_stream<T: Serialize>(
+	pending: PendingSubscriptionSink,
+	items: impl Stream<Item = T> + Send,
+) -> SubscriptionResult {
+	let sink = pending.accept().await?;
+	futures::pin_mut!(items);
+
+	loop {
+		tokio::select! {
+			_ = sink.closed() => break,
+			item = items.next() => match item {
+				Some(item) => sink.send(SubscriptionMessage::from_json(&item)?).await?,
+				None => break,
+			},
+		}
+	}
+
+	Ok(())
+}
@@ -1,5 +1,9 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
+use crate::{
+	indexer::{IndexerConfig, TransferIndexer},
+	rpc::bridge::BridgeConfig,
+};
 use node_template_runtime::{self, opaque::Block, RuntimeApi};
 use sc_client_api::{BlockBackend, ExecutorProvider};
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
@@ -36,6 +40,20 @@ type FullClient =
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
+/// Builds the client alone, without the import queue, the transaction pool or the consensus
+/// components of `new_partial`, e.g. to read the chain from a command.
+pub fn new_client(config: &Configuration) -> Result<Arc<FullClient>, ServiceError> {
+	let executor = NativeElseWasmExecutor::<ExecutorDispatch>::new(
+		config.wasm_method,
+		config.default_heap_pages,
+		config.max_runtime_instances,
+		config.runtime_cache_size,
+	);
+	let (client, ..) = sc_service::new_full_parts::<Block, RuntimeApi, _>(config, None, executor)?;
+
+	Ok(Arc::new(client))
+}
+
 pub fn new_partial(
 	config: &Configuration,
 ) -> Result<
@@ -157,7 +175,21 @@ fn remote_keystore(_url: &String) -> Result<Arc<LocalKeystore>, &'static str> {
 }
 
 /// Builds a new service for a full client.
-pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError> {
+pub fn new_full(
+	mut config: Configuration,
+	erc20_indexer: Option<IndexerConfig>,
+	erc20_rpc: BridgeConfig,
+) -> Result<TaskManager, ServiceError> {
+	// the indexer reads the events of every finalized block from its state, which a pruning node
+	// may have discarded by the time it gets to the block
+	if erc20_indexer.is_some() && !config.state_pruning.is_archive() {
+		return Err(ServiceError::Other(
+			"The ERC20 indexer needs the state of every finalized block, run the node with \
+			 `--pruning archive` (or `--pruning archive-canonical`)"
+				.into(),
+		))
+	}
+
 	let sc_service::PartialComponents {
 		client,
 		backend,
@@ -221,15 +253,41 @@ pub fn new_full(mut config: Configuration) -> Result<TaskManager, ServiceError>
 	let enable_grandpa = !config.disable_grandpa;
 	let prometheus_registry = config.prometheus_registry().cloned();
 
+	let erc20_indexer = match erc20_indexer {
+		Some(indexer_config) => {
+			let path = config
+				.base_path
+				.as_ref()
+				.ok_or_else(|| ServiceError::Other("The ERC20 indexer needs a base path".into()))?
+				.config_dir(config.chain_spec.id())
+				.join("erc20_index");
+			let indexer = Arc::new(TransferIndexer::open(&path, &indexer_config)?);
+
+			task_manager.spawn_handle().spawn_blocking(
+				"erc20-indexer",
+				None,
+				indexer.clone().run(client.clone()),
+			);
+			Some(indexer)
+		},
+		None => None,
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
 
-		Box::new(move |deny_unsafe, _| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: pool.clone(), deny_unsafe };
-
-			Ok(crate::rpc::create_full(deps))
+		Box::new(move |deny_unsafe, subscription_executor| {
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				deny_unsafe,
+				subscription_executor,
+				erc20_indexer: erc20_indexer.clone(),
+				erc20_rpc: erc20_rpc.clone(),
+			};
+
+			crate::rpc::create_full::<_, _, FullBackend>(deps).map_err(ServiceError::Other)
 		})
 	};
 
@@ -0,0 +1,73 @@
+//! Helpers to run a dev node as a child process.
+
+use std::{
+	io::{BufRead, BufReader},
+	process::{Child, Command, Stdio},
+	sync::mpsc,
+	thread,
+	time::{Duration, Instant},
+};
+
+/// Path of the node binary, built by cargo along with the integration tests
+pub const NODE: &str = env!("CARGO_BIN_EXE_node-template");
+
+/// `node-template --dev --tmp` running as a child process, killed on drop.
+pub struct Node {
+	child: Child,
+	/// URL of the WebSocket RPC server
+	pub ws_url: String,
+}
+
+impl Node {
+	/// Starts a dev node on random ports, with the ERC20 indexer enabled, and waits until its
+	/// WebSocket RPC server is listening.
+	pub fn start() -> Self {
+		let mut child = Command::new(NODE)
+			.args(["--dev", "--tmp", "--no-telemetry", "--no-prometheus", "--no-mdns"])
+			.args(["--port", "0", "--rpc-port", "0", "--ws-port", "0"])
+			.args(["--enable-erc20-indexer", "--pruning", "archive"])
+			.stdout(Stdio::null())
+			.stderr(Stdio::piped())
+			.spawn()
+			.expect("the node binary is built along with the tests; qed");
+
+		// the log is read until the end, so that the node never blocks on a full pipe
+		let stderr = child.stderr.take().expect("stderr is piped; qed");
+		let (lines, log) = mpsc::channel();
+		thread::spawn(move || {
+			for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+				let _ = lines.send(line);
+			}
+		});
+
+		let deadline = Instant::now() + Duration::from_secs(120);
+		let ws_url = loop {
+			let line = match log.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+				Ok(line) => line,
+				Err(e) => {
+					let _ = child.kill();
+					panic!("The node didn't start its RPC server: {:?}", e);
+				},
+			};
+
+			if let Some(addr) = address_after(&line, "Running JSON-RPC WS server: addr=") {
+				break format!("ws://{}", addr)
+			}
+		};
+
+		Self { child, ws_url }
+	}
+}
+
+impl Drop for Node {
+	fn drop(&mut self) {
+		let _ = self.child.kill();
+		let _ = self.child.wait();
+	}
+}
+
+/// Address logged right after `marker` in `line`, if any
+fn address_after(line: &str, marker: &str) -> Option<String> {
+	let (_, rest) = line.split_once(marker)?;
+	rest.split(|c: char| c == ',' || c.is_whitespace()).next().map(Into::into)
+}
@@ -0,0 +1,104 @@
+//! End to end test of the ERC20 pallet on a dev node: a transfer is signed and submitted with the
+//! `erc20 tx` subcommand, and then checked in the finalized storage and events, and in the
+//! transfers index served by the ERC20 RPC.
+
+mod common;
+
+use std::{
+	process::Command,
+	time::{Duration, Instant},
+};
+
+use codec::Decode;
+use jsonrpsee::{
+	core::client::ClientT,
+	rpc_params,
+	ws_client::{WsClient, WsClientBuilder},
+};
+use node_template::erc20_storage;
+use node_template_runtime::{pallet_erc20, AccountId, Event, Hash, Header};
+use sp_core::{crypto::Ss58Codec, sr25519, storage::StorageKey, twox_128, Bytes, Pair, U256};
+
+type EventRecord = frame_system::EventRecord<Event, Hash>;
+
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+fn account(seed: &str) -> AccountId {
+	let pair = sr25519::Pair::from_string(&format!("//{}", seed), None).unwrap();
+	pair.public().into()
+}
+
+/// Value at `key` in the state of block `at`, the default value if there is none
+async fn storage<T: Decode + Default>(client: &WsClient, key: StorageKey, at: Hash) -> T {
+	let value: Option<Bytes> =
+		client.request("state_getStorage", rpc_params![key, at]).await.unwrap();
+	value.map(|value| T::decode(&mut &value[..]).unwrap()).unwrap_or_default()
+}
+
+async fn finalized_balance(client: &WsClient, account: &AccountId) -> (Hash, U256) {
+	let head: Hash = client.request("chain_getFinalizedHead", rpc_params![]).await.unwrap();
+	(head, storage(client, erc20_storage::balance_key(account), head).await)
+}
+
+#[tokio::test]
+async fn transfer_is_finalized_and_indexed() {
+	let node = common::Node::start();
+	let client = WsClientBuilder::default().build(&node.ws_url).await.unwrap();
+	let (alice, bob) = (account("Alice"), account("Bob"));
+
+	let (_, balance_before) = finalized_balance(&client, &bob).await;
+
+	let output = Command::new(common::NODE)
+		.args(["erc20", "tx", "transfer", "--suri", "//Alice", "--amount", "1.5"])
+		.args(["--to", &bob.to_ss58check(), "--url", &node.ws_url])
+		.output()
+		.unwrap();
+	assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+	// 1.5 tokens with 18 decimals
+	let amount = U256::from(15) * U256::exp10(17);
+
+	let deadline = Instant::now() + TIMEOUT;
+	let finalized = loop {
+		let (head, balance) = finalized_balance(&client, &bob).await;
+		if balance == balance_before + amount {
+			break head
+		}
+		assert!(Instant::now() < deadline, "the transfer wasn't finalized in time");
+		tokio::time::sleep(Duration::from_secs(1)).await;
+	};
+
+	// walk back from the finalized head to the block of the transfer
+	let events_key = StorageKey([twox_128(b"System"), twox_128(b"Events")].concat());
+	let expected = pallet_erc20::Event::Transfer { from: alice.clone(), to: bob.clone(), amount };
+	let mut hash = finalized;
+	let number = loop {
+		let header: Header = client.request("chain_getHeader", rpc_params![hash]).await.unwrap();
+		let events: Vec<EventRecord> = storage(&client, events_key.clone(), hash).await;
+		if events.iter().any(|record| record.event == Event::Erc20(expected.clone())) {
+			break header.number
+		}
+		assert!(header.number > 0, "no Transfer event in the finalized chain");
+		hash = header.parent_hash;
+	};
+
+	// the indexer follows finality, so it may lag slightly behind
+	let deadline = Instant::now() + TIMEOUT;
+	loop {
+		let page: serde_json::Value = client
+			.request("erc20_transfersByAccount", rpc_params![bob.to_ss58check()])
+			.await
+			.unwrap();
+		let transfers = page["transfers"].as_array().unwrap();
+		if let Some(transfer) = transfers.iter().find(|transfer| transfer["blockNumber"] == number)
+		{
+			assert_eq!(transfer["blockHash"], serde_json::to_value(hash).unwrap());
+			assert_eq!(transfer["from"], alice.to_ss58check());
+			assert_eq!(transfer["to"], bob.to_ss58check());
+			assert_eq!(transfer["amount"], serde_json::to_value(amount).unwrap());
+			break
+		}
+		assert!(Instant::now() < deadline, "the transfer wasn't indexed in time");
+		tokio::time::sleep(Duration::from_secs(1)).await;
+	}
+}
@@ -0,0 +1,11 @@
+//! Runs random sequences of token operations against both the pallet and the reference model.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pallet_erc20::model::{self, Op};
+
+fuzz_target!(|input: ([u8; model::ACCOUNTS as usize], Vec<Op>)| {
+	let (genesis, ops) = input;
+	model::check(&genesis, &ops);
+});
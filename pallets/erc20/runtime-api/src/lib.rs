@@ -0,0 +1,43 @@
+//! Runtime API definition for the erc20 pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+pub use pallet_erc20::{EthereumAddress, TransferSimulation};
+use sp_core::U256;
+use sp_runtime::DispatchError;
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	/// API to query the state of the erc20 pallet (missing storage entries are reported as zeros)
+	///
+	/// Version 2 added `simulate_transfer` and `eth_call`.
+	#[api_version(2)]
+	pub trait Erc20Api<AccountId, Event> where AccountId: Codec, Event: Codec {
+		/// Amount of tokens in existence
+		fn total_supply() -> U256;
+
+		/// Amount of tokens owned by `account`
+		fn balance_of(account: AccountId) -> U256;
+
+		/// Remaining number of tokens that `spender` will be allowed to spend on behalf of `owner`
+		fn allowance(owner: AccountId, spender: AccountId) -> U256;
+
+		/// Simulates a transfer of `amount` tokens from `from` to `to` without changing the state.
+		/// The transfer is made by `spender` with `transfer_from`, or by `from` itself with
+		/// `transfer` if there is no `spender`
+		fn simulate_transfer(
+			from: AccountId,
+			to: AccountId,
+			amount: U256,
+			spender: Option<AccountId>,
+		) -> Result<TransferSimulation<Event>, DispatchError>;
+
+		/// Executes the ABI-encoded `calldata` of an ERC20 function made by `from` like `eth_call`,
+		/// without changing the state, and returns the ABI-encoded result
+		fn eth_call(
+			from: Option<EthereumAddress>,
+			calldata: Vec<u8>,
+		) -> Result<Vec<u8>, DispatchError>;
+	}
+}
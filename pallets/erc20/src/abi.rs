@@ -0,0 +1,204 @@
+//! Ethereum ABI encoding of the ERC20 interface, so that Solidity tooling can be used against the
+//! pallet.
+//!
+//! The calldata of the standard ERC20 functions is decoded into `Erc20Function`, whose addresses
+//! are resolved to accounts by the pallet (see `Pallet::abi_call` and `Pallet::eth_call`). The
+//! results are encoded the way an ERC20 contract returns them.
+
+use crate::EthereumAddress;
+use sp_core::U256;
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+/// First 4 bytes of the keccak-256 hash of the signature of a function, which select it
+pub type Selector = [u8; 4];
+
+/// Selector of `name()`
+pub const NAME: Selector = [0x06, 0xfd, 0xde, 0x03];
+/// Selector of `symbol()`
+pub const SYMBOL: Selector = [0x95, 0xd8, 0x9b, 0x41];
+/// Selector of `decimals()`
+pub const DECIMALS: Selector = [0x31, 0x3c, 0xe5, 0x67];
+/// Selector of `totalSupply()`
+pub const TOTAL_SUPPLY: Selector = [0x18, 0x16, 0x0d, 0xdd];
+/// Selector of `balanceOf(address)`
+pub const BALANCE_OF: Selector = [0x70, 0xa0, 0x82, 0x31];
+/// Selector of `allowance(address,address)`
+pub const ALLOWANCE: Selector = [0xdd, 0x62, 0xed, 0x3e];
+/// Selector of `transfer(address,uint256)`
+pub const TRANSFER: Selector = [0xa9, 0x05, 0x9c, 0xbb];
+/// Selector of `approve(address,uint256)`
+pub const APPROVE: Selector = [0x09, 0x5e, 0xa7, 0xb3];
+/// Selector of `transferFrom(address,address,uint256)`
+pub const TRANSFER_FROM: Selector = [0x23, 0xb8, 0x72, 0xdd];
+
+/// Size of an ABI word, every static argument and result takes one
+const WORD: usize = 32;
+
+/// Call of a function of the ERC20 interface, decoded from its calldata
+#[derive(Clone, Eq, PartialEq, RuntimeDebug)]
+pub enum Erc20Function {
+	/// `name()`
+	Name,
+	/// `symbol()`
+	Symbol,
+	/// `decimals()`
+	Decimals,
+	/// `totalSupply()`
+	TotalSupply,
+	/// `balanceOf(owner)`
+	BalanceOf { owner: EthereumAddress },
+	/// `allowance(owner, spender)`
+	Allowance { owner: EthereumAddress, spender: EthereumAddress },
+	/// `transfer(to, amount)`
+	Transfer { to: EthereumAddress, amount: U256 },
+	/// `approve(spender, amount)`
+	Approve { spender: EthereumAddress, amount: U256 },
+	/// `transferFrom(from, to, amount)`
+	TransferFrom { from: EthereumAddress, to: EthereumAddress, amount: U256 },
+}
+
+/// Reason why calldata could not be decoded
+#[derive(Clone, Copy, Eq, PartialEq, RuntimeDebug)]
+pub enum AbiError {
+	/// The calldata doesn't start with the selector of an ERC20 function
+	UnknownSelector,
+	/// Arguments are missing, or are not valid values of their types
+	InvalidArguments,
+}
+
+impl Erc20Function {
+	/// Decodes the calldata of a function call. Like in Solidity contracts, the bytes following
+	/// the arguments are ignored
+	pub fn decode(calldata: &[u8]) -> Result<Self, AbiError> {
+		if calldata.len() < 4 {
+			return Err(AbiError::UnknownSelector)
+		}
+		let mut selector = [0; 4];
+		selector.copy_from_slice(&calldata[..4]);
+		let mut arguments = Arguments(&calldata[4..]);
+
+		Ok(match selector {
+			NAME => Self::Name,
+			SYMBOL => Self::Symbol,
+			DECIMALS => Self::Decimals,
+			TOTAL_SUPPLY => Self::TotalSupply,
+			BALANCE_OF => Self::BalanceOf { owner: arguments.address()? },
+			ALLOWANCE =>
+				Self::Allowance { owner: arguments.address()?, spender: arguments.address()? },
+			TRANSFER => Self::Transfer { to: arguments.address()?, amount: arguments.uint()? },
+			APPROVE => Self::Approve { spender: arguments.address()?, amount: arguments.uint()? },
+			TRANSFER_FROM => Self::TransferFrom {
+				from: arguments.address()?,
+				to: arguments.address()?,
+				amount: arguments.uint()?,
+			},
+			_ => return Err(AbiError::UnknownSelector),
+		})
+	}
+
+	/// Calldata of the function call, as sent by Solidity tooling
+	pub fn encode(&self) -> Vec<u8> {
+		let mut calldata = self.selector().to_vec();
+
+		match self {
+			Self::Name | Self::Symbol | Self::Decimals | Self::TotalSupply => {},
+			Self::BalanceOf { owner } => calldata.extend(encode_address(owner)),
+			Self::Allowance { owner, spender } => {
+				calldata.extend(encode_address(owner));
+				calldata.extend(encode_address(spender));
+			},
+			Self::Transfer { to: address, amount } | Self::Approve { spender: address, amount } => {
+				calldata.extend(encode_address(address));
+				calldata.extend(encode_uint(*amount));
+			},
+			Self::TransferFrom { from, to, amount } => {
+				calldata.extend(encode_address(from));
+				calldata.extend(encode_address(to));
+				calldata.extend(encode_uint(*amount));
+			},
+		}
+
+		calldata
+	}
+
+	/// Selector of the function
+	pub fn selector(&self) -> Selector {
+		match self {
+			Self::Name => NAME,
+			Self::Symbol => SYMBOL,
+			Self::Decimals => DECIMALS,
+			Self::TotalSupply => TOTAL_SUPPLY,
+			Self::BalanceOf { .. } => BALANCE_OF,
+			Self::Allowance { .. } => ALLOWANCE,
+			Self::Transfer { .. } => TRANSFER,
+			Self::Approve { .. } => APPROVE,
+			Self::TransferFrom { .. } => TRANSFER_FROM,
+		}
+	}
+
+	/// Whether the function only reads the state (a `view` function in Solidity)
+	pub fn is_view(&self) -> bool {
+		!matches!(self, Self::Transfer { .. } | Self::Approve { .. } | Self::TransferFrom { .. })
+	}
+}
+
+/// Encodes a `uint256` (and any smaller unsigned integer)
+pub fn encode_uint(value: U256) -> Vec<u8> {
+	let mut word = sp_std::vec![0; WORD];
+	value.to_big_endian(&mut word);
+	word
+}
+
+/// Encodes a `bool`
+pub fn encode_bool(value: bool) -> Vec<u8> {
+	encode_uint(U256::from(value as u8))
+}
+
+/// Encodes an `address`
+pub fn encode_address(address: &EthereumAddress) -> Vec<u8> {
+	address.to_word().as_bytes().to_vec()
+}
+
+/// Encodes a `string` returned alone: the offset of its data, its length and its bytes padded to
+/// a whole number of words
+pub fn encode_string(value: &str) -> Vec<u8> {
+	let bytes = value.as_bytes();
+	let padded_len = (bytes.len() + WORD - 1) / WORD * WORD;
+
+	let mut encoded = encode_uint(WORD.into());
+	encoded.extend(encode_uint(bytes.len().into()));
+	encoded.extend(bytes);
+	encoded.resize(2 * WORD + padded_len, 0);
+	encoded
+}
+
+/// Arguments of a call, read one word at a time
+struct Arguments<'a>(&'a [u8]);
+
+impl<'a> Arguments<'a> {
+	fn word(&mut self) -> Result<&'a [u8], AbiError> {
+		if self.0.len() < WORD {
+			return Err(AbiError::InvalidArguments)
+		}
+		let (word, rest) = self.0.split_at(WORD);
+		self.0 = rest;
+		Ok(word)
+	}
+
+	/// An `address`, which must be left-padded with zeros
+	fn address(&mut self) -> Result<EthereumAddress, AbiError> {
+		let word = self.word()?;
+		if word[..WORD - 20].iter().any(|byte| *byte != 0) {
+			return Err(AbiError::InvalidArguments)
+		}
+
+		let mut address = [0; 20];
+		address.copy_from_slice(&word[WORD - 20..]);
+		Ok(EthereumAddress(address))
+	}
+
+	fn uint(&mut self) -> Result<U256, AbiError> {
+		Ok(U256::from_big_endian(self.word()?))
+	}
+}
@@ -0,0 +1,281 @@
+//! Differential tests of the pallet against the OpenZeppelin model.
+//!
+//! `replay` runs the same trace against both `mock::new_test_ext` and an `OzErc20` with the same
+//! genesis state, and reports the first step after which they disagree on the outcome, the events,
+//! the total supply, a balance or an allowance.
+
+use crate::{
+	mock::*,
+	openzeppelin::{OzErc20, OzEvent, Revert, ZERO_ADDRESS},
+	Error,
+};
+use sp_core::U256;
+use sp_runtime::{DispatchError, DispatchResult};
+use std::collections::BTreeSet;
+
+/// Operation of a trace, `sender` being the signer of the extrinsic or `msg.sender`
+#[derive(Clone, Debug)]
+enum Step {
+	Transfer { sender: u64, to: u64, amount: U256 },
+	Approve { sender: u64, spender: u64, amount: U256 },
+	TransferFrom { sender: u64, from: u64, to: u64, amount: U256 },
+	Mint { account: u64, amount: U256 },
+	Burn { account: u64, amount: U256 },
+}
+
+/// Disagreement between the pallet and the model
+#[derive(Debug, PartialEq)]
+enum Divergence {
+	Outcome { pallet: DispatchResult, openzeppelin: Result<(), Revert> },
+	Events { pallet: Vec<OzEvent>, openzeppelin: Vec<OzEvent> },
+	TotalSupply { pallet: U256, openzeppelin: U256 },
+	Balance { account: u64, pallet: U256, openzeppelin: U256 },
+	Allowance { owner: u64, spender: u64, pallet: U256, openzeppelin: U256 },
+}
+
+/// Replays `steps`, returning the index of the first step after which the pallet and the model
+/// diverge along with all their divergences at that point
+fn replay(steps: &[Step]) -> Result<(), (usize, Vec<Divergence>)> {
+	new_test_ext().execute_with(|| {
+		let mut oz =
+			OzErc20::new(Erc20::total_supply().unwrap_or_default(), crate::Balance::<Test>::iter());
+		let mut accounts = crate::Balance::<Test>::iter_keys().collect::<BTreeSet<_>>();
+		accounts.insert(ZERO_ADDRESS);
+
+		for (i, step) in steps.iter().enumerate() {
+			accounts.extend(step_accounts(step));
+
+			System::reset_events();
+			let (pallet, openzeppelin) = match step.clone() {
+				Step::Transfer { sender, to, amount } => (
+					Erc20::transfer(Origin::signed(sender), to, amount),
+					oz.transfer(sender, to, amount),
+				),
+				Step::Approve { sender, spender, amount } => (
+					Erc20::approve(Origin::signed(sender), spender, amount),
+					oz.approve(sender, spender, amount),
+				),
+				Step::TransferFrom { sender, from, to, amount } => (
+					Erc20::transfer_from(Origin::signed(sender), from, to, amount),
+					oz.transfer_from(sender, from, to, amount),
+				),
+				Step::Mint { account, amount } =>
+					(Erc20::mint(account, amount), oz.mint(account, amount)),
+				Step::Burn { account, amount } =>
+					(Erc20::burn(account, amount), oz.burn(account, amount)),
+			};
+
+			let mut divergences = vec![];
+
+			let outcomes_match = match (&pallet, &openzeppelin) {
+				(Ok(()), Ok(_)) => true,
+				(Err(error), Err(revert)) => revert_of(*error) == Some(*revert),
+				_ => false,
+			};
+			if !outcomes_match {
+				divergences.push(Divergence::Outcome {
+					pallet,
+					openzeppelin: openzeppelin.as_ref().map(|_| ()).map_err(|revert| *revert),
+				});
+			}
+
+			let pallet_events = pallet_events();
+			let openzeppelin_events = openzeppelin.unwrap_or_default();
+			if pallet_events != openzeppelin_events {
+				divergences.push(Divergence::Events {
+					pallet: pallet_events,
+					openzeppelin: openzeppelin_events,
+				});
+			}
+
+			let total_supply = Erc20::total_supply().unwrap_or_default();
+			if total_supply != oz.total_supply() {
+				divergences.push(Divergence::TotalSupply {
+					pallet: total_supply,
+					openzeppelin: oz.total_supply(),
+				});
+			}
+			for &account in &accounts {
+				let balance = Erc20::balance_of(account).unwrap_or_default();
+				if balance != oz.balance_of(account) {
+					divergences.push(Divergence::Balance {
+						account,
+						pallet: balance,
+						openzeppelin: oz.balance_of(account),
+					});
+				}
+			}
+			for &owner in &accounts {
+				for &spender in &accounts {
+					let allowance = Erc20::allowance(owner, spender).unwrap_or_default();
+					if allowance != oz.allowance(owner, spender) {
+						divergences.push(Divergence::Allowance {
+							owner,
+							spender,
+							pallet: allowance,
+							openzeppelin: oz.allowance(owner, spender),
+						});
+					}
+				}
+			}
+
+			if !divergences.is_empty() {
+				return Err((i, divergences))
+			}
+		}
+
+		Ok(())
+	})
+}
+
+fn step_accounts(step: &Step) -> Vec<u64> {
+	match *step {
+		Step::Transfer { sender, to, .. } => vec![sender, to],
+		Step::Approve { sender, spender, .. } => vec![sender, spender],
+		Step::TransferFrom { sender, from, to, .. } => vec![sender, from, to],
+		Step::Mint { account, .. } | Step::Burn { account, .. } => vec![account],
+	}
+}
+
+/// Revert of the contract corresponding to an error of the pallet
+fn revert_of(error: DispatchError) -> Option<Revert> {
+	let revert = if error == Error::<Test>::InsufficientFunds.into() {
+		Revert::TransferExceedsBalance
+	} else if error == Error::<Test>::InsufficientAllowance.into() {
+		Revert::InsufficientAllowance
+	} else if error == Error::<Test>::BurnExceedsBalance.into() {
+		Revert::BurnExceedsBalance
+	} else if error == Error::<Test>::Overflow.into() {
+		Revert::ArithmeticOverflow
+	} else {
+		return None
+	};
+
+	Some(revert)
+}
+
+/// Events of the pallet as `IERC20` events
+fn pallet_events() -> Vec<OzEvent> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			Event::Erc20(event) => OzEvent::from_event(event),
+			_ => None,
+		})
+		.collect()
+}
+
+#[test]
+fn test_differential_common_operations() {
+	assert_eq!(
+		replay(&[
+			Step::Transfer { sender: 1, to: 2, amount: 10.into() },
+			Step::Transfer { sender: 1, to: 1, amount: 100.into() },
+			Step::Transfer { sender: 2, to: 4, amount: 101.into() },
+			Step::Transfer { sender: 4, to: 2, amount: 1.into() },
+			Step::Approve { sender: 1, spender: 4, amount: 50.into() },
+			Step::Approve { sender: 1, spender: 4, amount: 20.into() },
+			Step::TransferFrom { sender: 4, from: 1, to: 5, amount: 21.into() },
+			Step::TransferFrom { sender: 5, from: 1, to: 5, amount: 1.into() },
+			Step::Approve { sender: 2, spender: 4, amount: U256::max_value() },
+			Step::TransferFrom { sender: 4, from: 2, to: 5, amount: 40.into() },
+			Step::TransferFrom { sender: 4, from: 2, to: 5, amount: 61.into() },
+			Step::Mint { account: 5, amount: 1_000.into() },
+			Step::Burn { account: 5, amount: 1_040.into() },
+			Step::Burn { account: 5, amount: 1.into() },
+			Step::Approve { sender: 1, spender: 4, amount: 0.into() },
+		]),
+		Ok(())
+	);
+}
+
+#[test]
+fn test_differential_zero_address() {
+	// the pallet has no zero address, so it lets account 0 receive and approve tokens
+	assert_eq!(
+		replay(&[Step::Transfer { sender: 1, to: ZERO_ADDRESS, amount: 10.into() }]),
+		Err((
+			0,
+			vec![
+				Divergence::Outcome { pallet: Ok(()), openzeppelin: Err(Revert::TransferToZero) },
+				Divergence::Events {
+					pallet: vec![OzEvent::Transfer { from: 1, to: ZERO_ADDRESS, value: 10.into() }],
+					openzeppelin: vec![],
+				},
+				Divergence::Balance { account: 0, pallet: 10.into(), openzeppelin: 0.into() },
+				Divergence::Balance { account: 1, pallet: 100.into(), openzeppelin: 110.into() },
+			]
+		))
+	);
+	assert_eq!(
+		replay(&[Step::Approve { sender: 1, spender: ZERO_ADDRESS, amount: 10.into() }]),
+		Err((
+			0,
+			vec![
+				Divergence::Outcome { pallet: Ok(()), openzeppelin: Err(Revert::ApproveToZero) },
+				Divergence::Events {
+					pallet: vec![OzEvent::Approval { owner: 1, spender: 0, value: 10.into() }],
+					openzeppelin: vec![],
+				},
+				Divergence::Allowance {
+					owner: 1,
+					spender: 0,
+					pallet: 10.into(),
+					openzeppelin: 0.into()
+				},
+			]
+		))
+	);
+}
+
+#[test]
+fn test_differential_transfer_from_event_order() {
+	// OpenZeppelin spends the allowance before transferring, the pallet the other way round
+	assert_eq!(
+		replay(&[
+			Step::Approve { sender: 1, spender: 4, amount: 50.into() },
+			Step::TransferFrom { sender: 4, from: 1, to: 5, amount: 20.into() },
+		]),
+		Err((
+			1,
+			vec![Divergence::Events {
+				pallet: vec![
+					OzEvent::Transfer { from: 1, to: 5, value: 20.into() },
+					OzEvent::Approval { owner: 1, spender: 4, value: 30.into() },
+				],
+				openzeppelin: vec![
+					OzEvent::Approval { owner: 1, spender: 4, value: 30.into() },
+					OzEvent::Transfer { from: 1, to: 5, value: 20.into() },
+				],
+			}]
+		))
+	);
+}
+
+#[test]
+fn test_differential_unchecked_arithmetic() {
+	// account 3 owns U256::max_value() tokens, which OpenZeppelin doesn't expect since the total
+	// supply is lower: its unchecked addition wraps around where the pallet fails
+	assert_eq!(
+		replay(&[Step::Transfer { sender: 1, to: 3, amount: 10.into() }]),
+		Err((
+			0,
+			vec![
+				Divergence::Outcome {
+					pallet: Err(Error::<Test>::Overflow.into()),
+					openzeppelin: Ok(()),
+				},
+				Divergence::Events {
+					pallet: vec![],
+					openzeppelin: vec![OzEvent::Transfer { from: 1, to: 3, value: 10.into() }],
+				},
+				Divergence::Balance { account: 1, pallet: 110.into(), openzeppelin: 100.into() },
+				Divergence::Balance {
+					account: 3,
+					pallet: U256::max_value(),
+					openzeppelin: 9.into()
+				},
+			]
+		))
+	);
+}
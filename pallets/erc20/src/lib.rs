@@ -5,20 +5,446 @@
 /// <https://docs.substrate.io/v3/runtime/frame>
 pub use pallet::*;
 
-#[cfg(test)]
-mod mock;
+pub mod abi;
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod mock;
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod model;
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(test)]
+mod proptests;
+
+#[cfg(test)]
+mod openzeppelin;
+
+#[cfg(test)]
+mod differential;
+
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+use abi::{AbiError, Erc20Function};
+use codec::{Decode, Encode};
+use frame_support::traits::Currency;
+use scale_info::TypeInfo;
+use sp_core::{H256, U256};
+use sp_runtime::RuntimeDebug;
+use sp_std::prelude::*;
+
+#[cfg(feature = "std")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Identifier of an escrow
+pub type EscrowId = u64;
+
+/// A conditional transfer, waiting to be either released to the recipient or returned to the sender
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Escrow<AccountId, BlockNumber> {
+	/// Account which has locked the tokens
+	pub from: AccountId,
+	/// Account to which the tokens are going to be released
+	pub to: AccountId,
+	/// Amount of locked tokens
+	pub amount: U256,
+	/// Block at which the escrow expires and the tokens are returned to `from`
+	pub expires_at: BlockNumber,
+}
+
+/// Escrow type used by a particular runtime
+pub type EscrowOf<T> =
+	Escrow<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// Hash of a secret locking an HTLC, along with the algorithm used to compute it
+///
+/// Both variants hash the secret directly, so the same secret can be used with HTLCs on other
+/// chains (sha2 ones are common on Bitcoin and Ethereum, blake2 ones on Substrate-based chains)
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum HashLock {
+	/// `blake2_256(preimage)`
+	Blake2_256(H256),
+	/// `sha2_256(preimage)`
+	Sha2_256(H256),
+}
+
+impl HashLock {
+	/// All the hash locks that can be unlocked by `preimage`
+	pub fn from_preimage(preimage: &[u8]) -> [HashLock; 2] {
+		[
+			HashLock::Blake2_256(sp_io::hashing::blake2_256(preimage).into()),
+			HashLock::Sha2_256(sp_io::hashing::sha2_256(preimage).into()),
+		]
+	}
+}
+
+/// Hashed time-locked contract: the tokens are transferred to `recipient` when the preimage of
+/// the hash lock is revealed before `timelock` block, otherwise they can be refunded to `sender`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Htlc<AccountId, BlockNumber> {
+	/// Account which has locked the tokens
+	pub sender: AccountId,
+	/// Account which gets the tokens when the preimage is revealed
+	pub recipient: AccountId,
+	/// Amount of locked tokens
+	pub amount: U256,
+	/// Block starting from which the tokens can no longer be claimed, but can be refunded
+	pub timelock: BlockNumber,
+}
+
+/// HTLC type used by a particular runtime
+pub type HtlcOf<T> =
+	Htlc<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// Identifier of a payment stream
+pub type StreamId = u64;
+
+/// A payment stream: `rate_per_block` tokens are streamed from `sender` to `recipient`
+/// every block between `start` and `stop`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Stream<AccountId, BlockNumber> {
+	/// Account which pays the tokens
+	pub sender: AccountId,
+	/// Account which receives the tokens
+	pub recipient: AccountId,
+	/// Amount of tokens streamed every block
+	pub rate_per_block: U256,
+	/// Block at which the streaming starts
+	pub start: BlockNumber,
+	/// Block at which the streaming stops
+	pub stop: BlockNumber,
+	/// Amount of tokens locked for the stream (`rate_per_block * (stop - start)`)
+	pub deposit: U256,
+	/// Amount of tokens already withdrawn by the recipient
+	pub withdrawn: U256,
+}
+
+/// Payment stream type used by a particular runtime
+pub type StreamOf<T> =
+	Stream<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// Identifier of a scheduled transfer
+pub type ScheduleId = u64;
+
+/// What to do with a scheduled transfer when one of its payments fails
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum FailurePolicy {
+	/// Count the failed payment as made and proceed with the next one
+	Skip,
+	/// Try to make the failed payment again in the next block
+	Retry,
+	/// Cancel the scheduled transfer altogether
+	Cancel,
+}
+
+/// A recurring transfer of `amount` tokens from `from` to `to` every `period` blocks
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct ScheduledTransfer<AccountId, BlockNumber> {
+	/// Account which pays the tokens
+	pub from: AccountId,
+	/// Account which receives the tokens
+	pub to: AccountId,
+	/// Amount of tokens transferred by each payment
+	pub amount: U256,
+	/// Block at which the next payment is due
+	pub next: BlockNumber,
+	/// Number of blocks between the payments
+	pub period: BlockNumber,
+	/// Number of payments left
+	pub remaining: u32,
+	/// What to do when a payment fails
+	pub on_failure: FailurePolicy,
+}
+
+/// Scheduled transfer type used by a particular runtime
+pub type ScheduledTransferOf<T> = ScheduledTransfer<
+	<T as frame_system::Config>::AccountId,
+	<T as frame_system::Config>::BlockNumber,
+>;
+
+/// An account controlled by `threshold` of `signatories`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Multisig<AccountId> {
+	/// Accounts which can propose and approve transfers (sorted)
+	pub signatories: Vec<AccountId>,
+	/// Number of approvals needed to execute a transfer
+	pub threshold: u16,
+}
+
+/// Identifier of a transfer proposal
+pub type ProposalId = u64;
+
+/// A transfer from a multisig account waiting for approvals of the signatories
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Proposal<AccountId, BlockNumber> {
+	/// Multisig account from which the tokens are going to be transferred
+	pub multisig: AccountId,
+	/// Account to which the tokens are going to be transferred
+	pub to: AccountId,
+	/// Amount of tokens to transfer
+	pub amount: U256,
+	/// Signatories that have approved the transfer
+	pub approvals: Vec<AccountId>,
+	/// Block starting from which the proposal can no longer be approved or executed
+	pub expires_at: BlockNumber,
+}
+
+/// Transfer proposal type used by a particular runtime
+pub type ProposalOf<T> =
+	Proposal<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// Outcome of a successful transfer simulated with `simulate_transfer`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct TransferSimulation<Event> {
+	/// Balance of the sender after the transfer
+	pub from_balance: U256,
+	/// Balance of the recipient after the transfer
+	pub to_balance: U256,
+	/// Remaining allowance of the spender, `None` if the sender makes the transfer
+	pub allowance: Option<U256>,
+	/// Events emitted by the transfer
+	pub events: Vec<Event>,
+}
+
+/// Transfer simulation type used by a particular runtime
+pub type TransferSimulationOf<T> = TransferSimulation<Event<T>>;
+
+/// Identifier of an airdrop
+pub type AirdropId = u64;
+
+/// Root of the Merkle tree of an airdrop, along with the hash function of the tree
+///
+/// Leaves are the hashes of the SCALE-encoded `(index: u32, account, amount: U256)`, and the two
+/// children of a node are sorted before being hashed together, so proofs are just the siblings
+/// on the path from the leaf to the root
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum MerkleRoot {
+	/// Tree hashed with `blake2_256`
+	Blake2_256(H256),
+	/// Tree hashed with `keccak_256`, as commonly done by the tools generating Ethereum airdrops
+	Keccak256(H256),
+}
+
+impl MerkleRoot {
+	/// The root hash itself
+	pub fn root(&self) -> H256 {
+		match self {
+			MerkleRoot::Blake2_256(root) | MerkleRoot::Keccak256(root) => *root,
+		}
+	}
+
+	/// Hash of `data` with the hash function of the tree
+	pub fn hash(&self, data: &[u8]) -> H256 {
+		match self {
+			MerkleRoot::Blake2_256(_) => sp_io::hashing::blake2_256(data).into(),
+			MerkleRoot::Keccak256(_) => sp_io::hashing::keccak_256(data).into(),
+		}
+	}
+
+	/// Leaf granting `amount` tokens to `account`
+	pub fn leaf<AccountId: Encode>(&self, index: u32, account: &AccountId, amount: U256) -> H256 {
+		self.hash(&(index, account, amount).encode())
+	}
+
+	/// Parent of two nodes, in any order
+	pub fn node(&self, a: H256, b: H256) -> H256 {
+		let (left, right) = if a <= b { (a, b) } else { (b, a) };
+		self.hash(&[left.as_bytes(), right.as_bytes()].concat())
+	}
+
+	/// Whether `proof` leads from `leaf` to the root
+	pub fn verify(&self, leaf: H256, proof: &[H256]) -> bool {
+		proof.iter().fold(leaf, |node, sibling| self.node(node, *sibling)) == self.root()
+	}
+}
+
+/// Tokens distributed to the leaves of a Merkle tree, which have to claim them before `expires_at`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct Airdrop<AccountId, BlockNumber> {
+	/// Account which has funded the airdrop, `None` if its tokens were minted
+	pub funder: Option<AccountId>,
+	/// Root of the tree of the recipients
+	pub merkle_root: MerkleRoot,
+	/// Amount of tokens distributed
+	pub total: U256,
+	/// Amount of tokens which were not claimed yet
+	pub remaining: U256,
+	/// Block starting from which the tokens can no longer be claimed, but can be swept
+	pub expires_at: BlockNumber,
+}
+
+/// Airdrop type used by a particular runtime
+pub type AirdropOf<T> =
+	Airdrop<<T as frame_system::Config>::AccountId, <T as frame_system::Config>::BlockNumber>;
+
+/// Balance type of the native currency used by a particular runtime
+pub type NativeBalanceOf<T> = <<T as Config>::NativeCurrency as Currency<
+	<T as frame_system::Config>::AccountId,
+>>::Balance;
+
+/// An Ethereum address, the last 20 bytes of the keccak-256 hash of the public key.
+/// Serialized as a `0x`-prefixed hex string
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, Ord, PartialOrd, RuntimeDebug, TypeInfo)]
+pub struct EthereumAddress(pub [u8; 20]);
+
+#[cfg(feature = "std")]
+impl Serialize for EthereumAddress {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		sp_core::bytes::serialize(&self.0, serializer)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for EthereumAddress {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let mut address = [0; 20];
+		sp_core::bytes::deserialize_check_len(
+			deserializer,
+			sp_core::bytes::ExpectedLen::Exact(&mut address),
+		)?;
+		Ok(Self(address))
+	}
+}
+
+impl EthereumAddress {
+	/// The address as an EVM word (left-padded with zeros), like in the topics of the logs
+	pub fn to_word(&self) -> H256 {
+		let mut word = H256::zero();
+		word.as_bytes_mut()[12..].copy_from_slice(&self.0);
+		word
+	}
+}
+
+/// A recoverable ECDSA signature over secp256k1, as produced by `eth_sign`: `r`, `s` and `v`
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub struct EcdsaSignature(pub [u8; 65]);
+
+/// Owner of an airdrop allocation, who has to sign its claim
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "std", serde(rename_all = "camelCase"))]
+pub enum ClaimOwner<AccountId> {
+	/// An account of the chain
+	Account(AccountId),
+	/// An Ethereum address, e.g. of a holder of the token on Ethereum
+	Ethereum(EthereumAddress),
+}
+
+/// Claim owner type used by a particular runtime
+pub type ClaimOwnerOf<T> = ClaimOwner<<T as frame_system::Config>::AccountId>;
+
+/// Proof that the owner of an allocation wants it to be claimed to some destination account
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum ClaimSignature<AccountId, Signature> {
+	/// Signature of the owner account over the claim message
+	Account {
+		/// Owner of the allocation
+		owner: AccountId,
+		/// Signature of the claim message
+		signature: Signature,
+	},
+	/// Signature of the claim message by the owner Ethereum address (with `eth_sign`, or
+	/// `personal_sign`), the address itself is recovered from the signature
+	Ethereum(EcdsaSignature),
+}
+
+/// Token operation signed by an Ethereum key, made on behalf of the account linked to its address
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum EthereumCall<AccountId> {
+	/// `transfer(to, amount)`
+	Transfer {
+		/// Account to which the tokens are sent
+		to: AccountId,
+		/// Amount of tokens to transfer
+		amount: U256,
+	},
+	/// `approve(spender, amount)`
+	Approve {
+		/// Account that is authorized to spend the tokens
+		spender: AccountId,
+		/// Amount of tokens allowed to be spent
+		amount: U256,
+	},
+	/// `transferFrom(from, to, amount)`
+	TransferFrom {
+		/// Account from which the tokens are deducted
+		from: AccountId,
+		/// Account to which the tokens are sent
+		to: AccountId,
+		/// Amount of tokens to transfer
+		amount: U256,
+	},
+}
+
+/// Ethereum call type used by a particular runtime
+pub type EthereumCallOf<T> = EthereumCall<<T as frame_system::Config>::AccountId>;
+
+/// Message signed by an Ethereum key: `EthereumPrefix` followed by the hex-encoded SCALE encoding
+/// of the payload, prefixed like `eth_sign` does
+#[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, TypeInfo)]
+pub enum EthereumPayload<AccountId> {
+	/// Consent to link the address to `account`
+	Link {
+		/// Account to which the address is linked
+		account: AccountId,
+	},
+	/// Transaction number `nonce` of the address
+	Transaction {
+		/// Number of transactions the address has submitted so far
+		nonce: u64,
+		/// Operation to make
+		call: EthereumCall<AccountId>,
+	},
+}
+
+/// Claim signature type used by a particular runtime
+pub type ClaimSignatureOf<T> =
+	ClaimSignature<<T as frame_system::Config>::AccountId, <T as Config>::Signature>;
+
 #[frame_support::pallet]
 pub mod pallet {
-	use frame_support::pallet_prelude::*;
-	use frame_system::pallet_prelude::*;
-	use sp_core::U256;
+	use super::*;
+	use frame_support::{
+		pallet_prelude::*,
+		storage::{with_transaction, TransactionOutcome},
+		traits::{ExistenceRequirement, UnfilteredDispatchable},
+		transactional, PalletId,
+	};
+	use frame_system::{pallet_prelude::*, RawOrigin};
+	use sp_io::{
+		hashing::{blake2_256, keccak_256},
+		KillStorageResult,
+	};
+	use sp_runtime::{
+		traits::{
+			AccountIdConversion, IdentifyAccount, One, Saturating, TrailingZeroInput, Verify, Zero,
+		},
+		SaturatedConversion,
+	};
+
+	/// Custom validity error of an unsigned claim whose owner has no (longer an) allocation
+	pub const NO_CLAIM: u8 = 1;
+
+	/// Custom validity error of an Ethereum transaction signed by an address not linked to any
+	/// account
+	pub const UNLINKED_ETHEREUM_ADDRESS: u8 = 2;
+
+	/// Custom validity error of an Ethereum transaction whose call would fail
+	pub const FAILING_ETHEREUM_CALL: u8 = 3;
+
+	/// Custom validity error of an Ethereum transaction sent less than
+	/// `EthereumTransactionInterval` blocks after the previous one of its address
+	pub const RATE_LIMITED_ETHEREUM_ADDRESS: u8 = 4;
+
+	/// Custom validity error of an Ethereum transaction whose call would change nothing, e.g. a
+	/// transfer of 0 tokens
+	pub const NO_OP_ETHEREUM_CALL: u8 = 5;
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
 	#[pallet::config]
@@ -37,6 +463,85 @@ pub mod pallet {
 		/// Number of decimals used to get its user representation (decimals() public function of ERC20)
 		#[pallet::constant]
 		type Decimals: Get<u8>;
+
+		/// Identifier of the pallet, used to derive the account holding locked tokens
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
+		/// Maximum number of escrows that can be automatically expired in a single block
+		#[pallet::constant]
+		type MaxEscrowExpiries: Get<u32>;
+
+		/// Maximum number of scheduled transfers an account can have at once
+		#[pallet::constant]
+		type MaxScheduledTransfers: Get<u32>;
+
+		/// Maximum weight that can be spent on executing scheduled transfers in a single block.
+		/// Transfers that did not fit are executed in the following blocks
+		#[pallet::constant]
+		type ScheduledTransfersWeight: Get<Weight>;
+
+		/// Maximum number of signatories of a multisig account
+		#[pallet::constant]
+		type MaxSignatories: Get<u32>;
+
+		/// Number of blocks during which a transfer proposal can be approved and executed
+		#[pallet::constant]
+		type ProposalLifetime: Get<Self::BlockNumber>;
+
+		/// Signature of an account over the message of an airdrop claim
+		type Signature: Verify<Signer = Self::Signer> + Parameter;
+
+		/// Signer of `Signature`, identifying the accounts of the runtime
+		type Signer: IdentifyAccount<AccountId = Self::AccountId>;
+
+		/// Prefix of the messages signed to claim an airdrop, which should be unique to the chain
+		/// (so that the signatures can't be replayed on another one)
+		#[pallet::constant]
+		type ClaimPrefix: Get<&'static [u8]>;
+
+		/// Priority of the unsigned claim transactions
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Number of blocks during which an unsigned claim transaction stays valid in the pool
+		#[pallet::constant]
+		type UnsignedLongevity: Get<TransactionLongevity>;
+
+		/// Origin which can create airdrops of newly minted tokens
+		type AirdropOrigin: EnsureOrigin<Self::Origin>;
+
+		/// Maximum number of words of the claimed bitmap of an airdrop removed by a single
+		/// `sweep_airdrop` call. Larger bitmaps are removed by the following calls
+		#[pallet::constant]
+		type MaxSweptBitmapWords: Get<u32>;
+
+		/// Prefix of the messages signed by Ethereum keys to link their address and to submit
+		/// transactions, which should be unique to the chain
+		#[pallet::constant]
+		type EthereumPrefix: Get<&'static [u8]>;
+
+		/// Priority of the unsigned Ethereum transactions, which should be well below the one of
+		/// the operational transactions since they are free
+		#[pallet::constant]
+		type EthereumTransactionPriority: Get<TransactionPriority>;
+
+		/// Minimum number of blocks between two Ethereum transactions of an address, which bounds
+		/// the free transactions an address can get into the blocks
+		#[pallet::constant]
+		type EthereumTransactionInterval: Get<Self::BlockNumber>;
+
+		/// Whether the `Transfer`, `Mint`, `Burn` and `Approval` events are mirrored by
+		/// `EthereumLog` events
+		type EthereumLogs: Get<bool>;
+
+		/// Native currency of the chain, which can be wrapped into tokens with `wrap`
+		type NativeCurrency: Currency<Self::AccountId>;
+
+		/// Number of decimals of the native currency, its amounts are scaled to `Decimals` when
+		/// wrapped
+		#[pallet::constant]
+		type NativeDecimals: Get<u8>;
 	}
 
 	#[pallet::pallet]
@@ -60,6 +565,138 @@ pub mod pallet {
 	pub type Allowance<T: Config> =
 		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, T::AccountId, U256>;
 
+	/// Escrows that were neither released nor returned to the sender yet
+	#[pallet::storage]
+	#[pallet::getter(fn escrow)]
+	pub type Escrows<T: Config> = StorageMap<_, Twox64Concat, EscrowId, EscrowOf<T>>;
+
+	/// Identifier to be assigned to the next created escrow
+	#[pallet::storage]
+	pub type NextEscrowId<T> = StorageValue<_, EscrowId, ValueQuery>;
+
+	/// Open escrows in which an account (first key) participates either as a sender or a recipient
+	#[pallet::storage]
+	pub type AccountEscrows<T: Config> =
+		StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Twox64Concat, EscrowId, ()>;
+
+	/// Hashed time-locked contracts which were neither claimed nor refunded yet
+	#[pallet::storage]
+	#[pallet::getter(fn htlc)]
+	pub type Htlcs<T: Config> = StorageMap<_, Blake2_128Concat, HashLock, HtlcOf<T>>;
+
+	/// Payment streams which were neither fully withdrawn nor cancelled yet
+	#[pallet::storage]
+	#[pallet::getter(fn stream)]
+	pub type Streams<T: Config> = StorageMap<_, Twox64Concat, StreamId, StreamOf<T>>;
+
+	/// Identifier to be assigned to the next created payment stream
+	#[pallet::storage]
+	pub type NextStreamId<T> = StorageValue<_, StreamId, ValueQuery>;
+
+	/// Recurring transfers which have payments left
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_transfer)]
+	pub type ScheduledTransfers<T: Config> =
+		StorageMap<_, Twox64Concat, ScheduleId, ScheduledTransferOf<T>>;
+
+	/// Identifier to be assigned to the next scheduled transfer
+	#[pallet::storage]
+	pub type NextScheduleId<T> = StorageValue<_, ScheduleId, ValueQuery>;
+
+	/// Number of scheduled transfers paid by an account
+	#[pallet::storage]
+	#[pallet::getter(fn scheduled_transfers_count)]
+	pub type ScheduledTransfersCount<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, u32, ValueQuery>;
+
+	/// Scheduled transfers (second key) with a payment due at a block (first key)
+	#[pallet::storage]
+	pub type TransferAgenda<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, T::BlockNumber, Twox64Concat, ScheduleId, ()>;
+
+	/// The earliest block which might still have unprocessed entries in `TransferAgenda`
+	#[pallet::storage]
+	pub type TransferAgendaCursor<T: Config> = StorageValue<_, T::BlockNumber>;
+
+	/// Multisig accounts created with `create_multisig_account`
+	#[pallet::storage]
+	#[pallet::getter(fn multisig)]
+	pub type Multisigs<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, Multisig<T::AccountId>>;
+
+	/// Transfer proposals which were neither executed nor removed yet
+	#[pallet::storage]
+	#[pallet::getter(fn proposal)]
+	pub type Proposals<T: Config> = StorageMap<_, Twox64Concat, ProposalId, ProposalOf<T>>;
+
+	/// Identifier to be assigned to the next transfer proposal
+	#[pallet::storage]
+	pub type NextProposalId<T> = StorageValue<_, ProposalId, ValueQuery>;
+
+	/// Airdrop allocations which were not claimed yet. They are not part of the total supply until
+	/// they are claimed
+	#[pallet::storage]
+	#[pallet::getter(fn claims)]
+	pub type Claims<T: Config> = StorageMap<_, Blake2_128Concat, ClaimOwnerOf<T>, U256>;
+
+	/// Airdrops which were not swept yet
+	#[pallet::storage]
+	#[pallet::getter(fn airdrop)]
+	pub type Airdrops<T: Config> = StorageMap<_, Twox64Concat, AirdropId, AirdropOf<T>>;
+
+	/// Identifier to be assigned to the next airdrop
+	#[pallet::storage]
+	pub type NextAirdropId<T> = StorageValue<_, AirdropId, ValueQuery>;
+
+	/// Bitmap of the claimed leaves of each airdrop, leaf `index` being the bit `index % 128` of
+	/// the word `index / 128`
+	#[pallet::storage]
+	pub type AirdropClaimedBitmap<T> =
+		StorageDoubleMap<_, Twox64Concat, AirdropId, Twox64Concat, u32, u128, ValueQuery>;
+
+	/// Swept airdrops whose claimed bitmap isn't entirely removed yet, `sweep_airdrop` removes
+	/// the rest of it
+	#[pallet::storage]
+	pub type AirdropsBeingSwept<T> = StorageMap<_, Twox64Concat, AirdropId, ()>;
+
+	/// Accounts on behalf of which the linked Ethereum addresses make transactions
+	#[pallet::storage]
+	#[pallet::getter(fn ethereum_account)]
+	pub type EthereumAccounts<T: Config> =
+		StorageMap<_, Blake2_128Concat, EthereumAddress, T::AccountId>;
+
+	/// Ethereum addresses linked to the accounts, the reverse of `EthereumAccounts`
+	#[pallet::storage]
+	#[pallet::getter(fn linked_ethereum_address)]
+	pub type EthereumAddresses<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, EthereumAddress>;
+
+	/// Number of transactions submitted by each Ethereum address, kept when it is unlinked
+	#[pallet::storage]
+	#[pallet::getter(fn ethereum_nonce)]
+	pub type EthereumNonces<T> = StorageMap<_, Blake2_128Concat, EthereumAddress, u64, ValueQuery>;
+
+	/// Block in which each Ethereum address has made its last transaction
+	#[pallet::storage]
+	pub type EthereumLastTransactions<T: Config> =
+		StorageMap<_, Blake2_128Concat, EthereumAddress, T::BlockNumber>;
+
+	/// Amount of tokens minted by `wrap` and not burnt by `unwrap` yet, the equivalent of the
+	/// native currency locked in the pallet account
+	#[pallet::storage]
+	#[pallet::getter(fn wrapped_supply)]
+	pub type WrappedSupply<T> = StorageValue<_, U256, ValueQuery>;
+
+	/// Escrows to be automatically returned to their senders at the beginning of a block
+	#[pallet::storage]
+	pub type EscrowExpiries<T: Config> = StorageMap<
+		_,
+		Twox64Concat,
+		T::BlockNumber,
+		BoundedVec<EscrowId, T::MaxEscrowExpiries>,
+		ValueQuery,
+	>;
+
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
 		/// Initial total supply of tokens
@@ -67,12 +704,18 @@ pub mod pallet {
 		pub total_supply: U256,
 		/// Initial balances of accounts
 		pub balances: Vec<(T::AccountId, U256)>,
+		/// Airdrop allocations, minted when claimed with `claim`
+		pub claims: Vec<(ClaimOwnerOf<T>, U256)>,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> Self {
-			Self { total_supply: Default::default(), balances: Default::default() }
+			Self {
+				total_supply: Default::default(),
+				balances: Default::default(),
+				claims: Default::default(),
+			}
 		}
 	}
 
@@ -83,6 +726,12 @@ pub mod pallet {
 			for (a, b) in &self.balances {
 				<Balance<T>>::insert(a, b);
 			}
+			for (owner, amount) in &self.claims {
+				<Claims<T>>::insert(owner, amount);
+			}
+			// the pallet account holds the wrapped native currency, endowing it with the
+			// existential deposit (which is never unwrapped) keeps it alive whatever the amounts
+			Pallet::<T>::endow_pallet_account();
 		}
 	}
 
@@ -124,6 +773,260 @@ pub mod pallet {
 			/// Amount of destroyed tokens
 			amount: U256,
 		},
+		/// Tokens were locked in an escrow
+		EscrowCreated {
+			/// Identifier of the new escrow
+			id: EscrowId,
+			/// Account which has locked the tokens
+			from: T::AccountId,
+			/// Account to which the tokens are going to be released
+			to: T::AccountId,
+			/// Amount of locked tokens
+			amount: U256,
+			/// Block at which the escrow expires
+			expires_at: T::BlockNumber,
+		},
+		/// Escrowed tokens were released to the recipient
+		EscrowReleased {
+			/// Identifier of the escrow
+			id: EscrowId,
+		},
+		/// Escrowed tokens were returned to the sender by the recipient
+		EscrowRefunded {
+			/// Identifier of the escrow
+			id: EscrowId,
+		},
+		/// Escrow has expired and the tokens were returned to the sender
+		EscrowExpired {
+			/// Identifier of the escrow
+			id: EscrowId,
+		},
+		/// Tokens were locked in an HTLC
+		HtlcLocked {
+			/// Hash lock of the HTLC
+			hashlock: HashLock,
+			/// Account which has locked the tokens
+			sender: T::AccountId,
+			/// Account which gets the tokens when the preimage is revealed
+			recipient: T::AccountId,
+			/// Amount of locked tokens
+			amount: U256,
+			/// Block starting from which the HTLC can be refunded
+			timelock: T::BlockNumber,
+		},
+		/// HTLC was claimed by revealing the preimage, the tokens were transferred to the
+		/// recipient
+		HtlcClaimed {
+			/// Hash lock of the HTLC
+			hashlock: HashLock,
+			/// The secret, which can now be used to claim the counterpart HTLC on the other chain
+			preimage: [u8; 32],
+		},
+		/// HTLC has expired and the tokens were returned to the sender
+		HtlcRefunded {
+			/// Hash lock of the HTLC
+			hashlock: HashLock,
+		},
+		/// Payment stream was created and its deposit was locked
+		StreamCreated {
+			/// Identifier of the new stream
+			id: StreamId,
+			/// Account which pays the tokens
+			sender: T::AccountId,
+			/// Account which receives the tokens
+			recipient: T::AccountId,
+			/// Amount of tokens streamed every block
+			rate_per_block: U256,
+			/// Block at which the streaming starts
+			start: T::BlockNumber,
+			/// Block at which the streaming stops
+			stop: T::BlockNumber,
+			/// Amount of locked tokens
+			deposit: U256,
+		},
+		/// Recipient has withdrawn the tokens streamed so far
+		StreamWithdrawn {
+			/// Identifier of the stream
+			id: StreamId,
+			/// Amount of withdrawn tokens
+			amount: U256,
+		},
+		/// Recurring transfer was scheduled
+		TransferScheduled {
+			/// Identifier of the scheduled transfer
+			id: ScheduleId,
+			/// Account which pays the tokens
+			from: T::AccountId,
+			/// Account which receives the tokens
+			to: T::AccountId,
+			/// Amount of tokens transferred by each payment
+			amount: U256,
+			/// Block at which the first payment is due
+			start: T::BlockNumber,
+			/// Number of blocks between the payments
+			period: T::BlockNumber,
+			/// Number of payments
+			count: u32,
+		},
+		/// A payment of a scheduled transfer was made
+		ScheduledTransferExecuted {
+			/// Identifier of the scheduled transfer
+			id: ScheduleId,
+		},
+		/// A payment of a scheduled transfer has failed
+		ScheduledTransferFailed {
+			/// Identifier of the scheduled transfer
+			id: ScheduleId,
+			/// Why the payment has failed
+			error: DispatchError,
+		},
+		/// Scheduled transfer was cancelled (either by the payer or due to a failed payment)
+		ScheduledTransferCancelled {
+			/// Identifier of the scheduled transfer
+			id: ScheduleId,
+		},
+		/// Multisig account was created
+		MultisigCreated {
+			/// The derived multisig account
+			account: T::AccountId,
+			/// Accounts controlling the multisig account (sorted)
+			signatories: Vec<T::AccountId>,
+			/// Number of approvals needed to execute a transfer
+			threshold: u16,
+		},
+		/// A transfer from a multisig account was proposed
+		ProposalCreated {
+			/// Identifier of the new proposal
+			id: ProposalId,
+			/// Multisig account from which the tokens are going to be transferred
+			multisig: T::AccountId,
+			/// Account to which the tokens are going to be transferred
+			to: T::AccountId,
+			/// Amount of tokens to transfer
+			amount: U256,
+			/// Block at which the proposal expires
+			expires_at: T::BlockNumber,
+		},
+		/// A signatory has approved a transfer proposal (proposing counts as approving)
+		ProposalApproved {
+			/// Identifier of the proposal
+			id: ProposalId,
+			/// Signatory which has approved the proposal
+			signatory: T::AccountId,
+			/// Number of approvals the proposal has now
+			approvals: u32,
+		},
+		/// Proposed transfer was executed
+		ProposalExecuted {
+			/// Identifier of the proposal
+			id: ProposalId,
+		},
+		/// Expired proposal was removed
+		ProposalExpired {
+			/// Identifier of the proposal
+			id: ProposalId,
+		},
+		/// Payment stream was cancelled, the deposit was split between the parties
+		StreamCancelled {
+			/// Identifier of the stream
+			id: StreamId,
+			/// Amount of streamed (but not yet withdrawn) tokens paid to the recipient
+			recipient_amount: U256,
+			/// Amount of not yet streamed tokens returned to the sender
+			sender_amount: U256,
+		},
+		/// An airdrop allocation was claimed, its tokens were minted to the destination account
+		Claimed {
+			/// Owner of the allocation
+			owner: ClaimOwnerOf<T>,
+			/// Account to which the tokens were minted
+			dest: T::AccountId,
+			/// Amount of tokens claimed
+			amount: U256,
+		},
+		/// Airdrop was created and its tokens were locked
+		AirdropCreated {
+			/// Identifier of the new airdrop
+			id: AirdropId,
+			/// Account which has funded the airdrop, `None` if its tokens were minted
+			funder: Option<T::AccountId>,
+			/// Root of the tree of the recipients
+			merkle_root: MerkleRoot,
+			/// Amount of tokens distributed
+			total: U256,
+			/// Block starting from which the tokens can no longer be claimed
+			expires_at: T::BlockNumber,
+		},
+		/// A leaf of the airdrop was claimed
+		AirdropClaimed {
+			/// Identifier of the airdrop
+			id: AirdropId,
+			/// Index of the leaf
+			index: u32,
+			/// Account which got the tokens
+			account: T::AccountId,
+			/// Amount of tokens claimed
+			amount: U256,
+		},
+		/// Expired airdrop was removed, its unclaimed tokens were returned to the funder or burnt
+		AirdropSwept {
+			/// Identifier of the airdrop
+			id: AirdropId,
+			/// Amount of unclaimed tokens
+			amount: U256,
+		},
+		/// Ethereum address was linked to an account
+		EthereumAddressLinked {
+			/// The account
+			account: T::AccountId,
+			/// The Ethereum address
+			address: EthereumAddress,
+		},
+		/// Ethereum address was unlinked from an account
+		EthereumAddressUnlinked {
+			/// The account
+			account: T::AccountId,
+			/// The Ethereum address
+			address: EthereumAddress,
+		},
+		/// Transaction signed by an Ethereum key was executed (successfully or not)
+		EthereumTransactionExecuted {
+			/// Address which has signed the transaction
+			address: EthereumAddress,
+			/// Nonce of the transaction
+			nonce: u64,
+			/// Outcome of the operation, whose changes were reverted if it failed
+			result: DispatchResult,
+		},
+		/// ERC20 event in the format of an EVM log, `Transfer(address,address,uint256)` or
+		/// `Approval(address,address,uint256)`. Accounts are represented by `ethereum_address`,
+		/// mints and burns are transfers from and to the zero address
+		EthereumLog {
+			/// Address of the token contract, see `ethereum_contract_address`
+			address: EthereumAddress,
+			/// Signature of the event followed by the indexed addresses
+			topics: Vec<H256>,
+			/// ABI-encoded amount
+			data: Vec<u8>,
+		},
+		/// Native tokens were locked and the equivalent tokens were minted
+		Wrapped {
+			/// Account which wrapped its native tokens
+			account: T::AccountId,
+			/// Amount of locked native tokens
+			native_amount: NativeBalanceOf<T>,
+			/// Amount of minted tokens
+			amount: U256,
+		},
+		/// Tokens were burnt and the equivalent native tokens were unlocked
+		Unwrapped {
+			/// Account which got the native tokens
+			account: T::AccountId,
+			/// Amount of unlocked native tokens
+			native_amount: NativeBalanceOf<T>,
+			/// Amount of burnt tokens
+			amount: U256,
+		},
 	}
 
 	// Errors inform users that something went wrong.
@@ -137,6 +1040,120 @@ pub mod pallet {
 		InsufficientAllowance,
 		/// An account has less tokens that were requested to be burnt
 		BurnExceedsBalance,
+		/// There is no open escrow with the given identifier
+		UnknownEscrow,
+		/// Only the sender of the escrow can release it
+		NotEscrowSender,
+		/// Only the recipient of the escrow can refund it
+		NotEscrowRecipient,
+		/// Escrow expiry block should be in the future
+		EscrowExpiryInPast,
+		/// Escrow can't be claimed as expired before its expiry block
+		EscrowNotExpired,
+		/// There is already an open HTLC with the same hash lock
+		HtlcAlreadyExists,
+		/// There is no open HTLC with the given hash lock (or matching the given preimage)
+		UnknownHtlc,
+		/// HTLC timelock block should be in the future
+		HtlcTimelockInPast,
+		/// HTLC can't be claimed after its timelock block
+		HtlcExpired,
+		/// HTLC can't be refunded before its timelock block
+		HtlcNotExpired,
+		/// There is no payment stream with the given identifier
+		UnknownStream,
+		/// Only the recipient of the stream can withdraw from it
+		NotStreamRecipient,
+		/// Only the sender or the recipient of the stream can cancel it
+		NotStreamParty,
+		/// Payment stream should not start in the past and should stop after it starts
+		InvalidStreamSchedule,
+		/// Payment stream deposit should be equal to `rate_per_block * (stop - start)`
+		StreamDepositMismatch,
+		/// There is no scheduled transfer with the given identifier
+		UnknownScheduledTransfer,
+		/// Only the payer can cancel a scheduled transfer
+		NotScheduledTransferPayer,
+		/// Scheduled transfer should start in the future and have non-zero period and count
+		InvalidSchedule,
+		/// An account has too many scheduled transfers
+		TooManyScheduledTransfers,
+		/// Multisig account should have at least one and at most `MaxSignatories` signatories
+		InvalidSignatoriesCount,
+		/// Signatories of a multisig account should be unique
+		DuplicateSignatories,
+		/// Multisig threshold should be between 1 and the number of signatories
+		InvalidThreshold,
+		/// Multisig account with the same signatories and threshold already exists
+		MultisigAlreadyExists,
+		/// The account is not a multisig account
+		UnknownMultisig,
+		/// Only signatories of the multisig account can propose, approve and execute its transfers
+		NotSignatory,
+		/// There is no transfer proposal with the given identifier
+		UnknownProposal,
+		/// The signatory has already approved the proposal
+		AlreadyApproved,
+		/// The proposal has expired
+		ProposalExpired,
+		/// The proposal can't be removed before it expires
+		ProposalNotExpired,
+		/// The proposal doesn't have enough approvals to be executed
+		NotEnoughApprovals,
+		/// The claim signature is invalid
+		InvalidClaimSignature,
+		/// The owner of the claim has no allocation, or has already claimed it
+		NoClaim,
+		/// There is no airdrop with the given identifier
+		UnknownAirdrop,
+		/// Airdrop expiry block should be in the future
+		AirdropExpiryInPast,
+		/// Airdrop can't be claimed after its expiry block
+		AirdropExpired,
+		/// Airdrop can't be swept before its expiry block
+		AirdropNotExpired,
+		/// The leaf of the airdrop has already been claimed
+		AirdropAlreadyClaimed,
+		/// The Merkle proof doesn't lead from the leaf to the root of the airdrop
+		InvalidMerkleProof,
+		/// The claimed leaves of the airdrop sum up to more than its total
+		AirdropExhausted,
+		/// The Ethereum signature is invalid
+		InvalidEthereumSignature,
+		/// The Ethereum address is already linked to an account
+		EthereumAddressAlreadyLinked,
+		/// The account already has a linked Ethereum address
+		AccountAlreadyLinked,
+		/// The account has no linked Ethereum address
+		AccountNotLinked,
+		/// The Ethereum address is not linked to any account
+		UnlinkedEthereumAddress,
+		/// The nonce of the Ethereum transaction is not the next one of its address
+		InvalidEthereumNonce,
+		/// The ABI-encoded calldata doesn't start with the selector of an ERC20 function
+		UnknownAbiSelector,
+		/// The arguments of the ABI-encoded calldata are missing or invalid
+		InvalidAbiArguments,
+		/// The ABI-encoded calldata is of a view function, which has no matching call
+		AbiViewFunction,
+		/// The amount can't be converted exactly between the decimals of the native currency and
+		/// of the token
+		InexactWrapAmount,
+		/// Less tokens are wrapped than requested to be unwrapped
+		InsufficientWrappedSupply,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: T::BlockNumber) -> Weight {
+			Self::expire_escrows(n).saturating_add(Self::execute_scheduled_transfers(n))
+		}
+
+		/// Endows the pallet account like the genesis does, for the chains started without it
+		fn on_runtime_upgrade() -> Weight {
+			Self::endow_pallet_account();
+			T::DbWeight::get().reads_writes(2, 2)
+		}
 	}
 
 	// private (non-dispatchable) functions
@@ -164,6 +1181,7 @@ pub mod pallet {
 			if from == to {
 				// no need to update balance in storage, this transfer should be an identity
 
+				Self::deposit_transfer_log(Some(&from), Some(&to), amount);
 				Self::deposit_event(Event::Transfer { from, to, amount });
 
 				return Ok(());
@@ -175,6 +1193,7 @@ pub mod pallet {
 			<Balance<T>>::insert(&from, from_balance);
 			<Balance<T>>::insert(&to, to_balance);
 
+			Self::deposit_transfer_log(Some(&from), Some(&to), amount);
 			Self::deposit_event(Event::Transfer { from, to, amount });
 
 			Ok(())
@@ -187,42 +1206,551 @@ pub mod pallet {
 		) -> DispatchResult {
 			<Allowance<T>>::insert(&owner, &spender, amount);
 
+			Self::deposit_approval_log(&owner, &spender, amount);
 			Self::deposit_event(Event::Approval { owner, spender, amount });
 
 			Ok(())
 		}
-	}
-
-	// non-dispatchable functions, but other pallets can call them
-	impl<T: Config> Pallet<T> {
-		/// Creates `amount` tokens and assigns them to `account`, increasing the total supply.
-		/// Emits a `Mint` event
-		pub fn mint(account: T::AccountId, amount: U256) -> DispatchResult {
-			let total_supply = <TotalSupply<T>>::get().unwrap_or(U256::zero());
-			let balance = <Balance<T>>::get(&account).unwrap_or(U256::zero());
 
-			let total_supply = total_supply.checked_add(amount).ok_or(Error::<T>::Overflow)?;
-			let balance = balance.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+		fn expire_escrows(n: T::BlockNumber) -> Weight {
+			let expiring = <EscrowExpiries<T>>::take(n);
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
 
-			<TotalSupply<T>>::put(total_supply);
-			<Balance<T>>::insert(&account, balance);
+			for id in expiring {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
 
-			Self::deposit_event(Event::Mint { account, amount });
+				// the escrow might have been already released or refunded
+				if let Some(escrow) = <Escrows<T>>::get(id) {
+					// a failed refund leaves the escrow open, so it can be settled with
+					// `claim_expired`
+					if Self::close_escrow(id, &escrow, escrow.from.clone()).is_ok() {
+						Self::deposit_event(Event::EscrowExpired { id });
+					}
+					weight = weight
+						.saturating_add(T::DbWeight::get().reads_writes(2, 5) + 20_000)
+						.saturating_add(ethereum_logs_weight::<T>(1));
+				}
+			}
 
-			Ok(())
+			weight
 		}
-		/// Destroys `amount` tokens from `account`, reducing the total supply.
-		/// Emits a `Burn` event
-		pub fn burn(account: T::AccountId, amount: U256) -> DispatchResult {
-			let total_supply = <TotalSupply<T>>::get().unwrap_or(U256::zero());
-			let balance = <Balance<T>>::get(&account).unwrap_or(U256::zero());
 
-			let total_supply = total_supply.checked_sub(amount).ok_or(Error::<T>::Overflow)?;
-			let balance = balance.checked_sub(amount).ok_or(Error::<T>::BurnExceedsBalance)?;
+		/// Makes the payments of scheduled transfers due at or before block `n`,
+		/// until `ScheduledTransfersWeight` is exhausted
+		fn execute_scheduled_transfers(n: T::BlockNumber) -> Weight {
+			let budget = T::ScheduledTransfersWeight::get();
+			let payment_weight =
+				T::DbWeight::get().reads_writes(4, 5) + 20_000 + ethereum_logs_weight::<T>(1);
+			let mut weight = T::DbWeight::get().reads_writes(1, 1);
 
-			<TotalSupply<T>>::put(total_supply);
-			<Balance<T>>::insert(&account, balance);
+			let mut block = <TransferAgendaCursor<T>>::get().unwrap_or(n);
+			while block <= n {
+				weight = weight.saturating_add(T::DbWeight::get().reads(1));
 
+				let mut agenda = <TransferAgenda<T>>::drain_prefix(block);
+				loop {
+					// check the budget before draining the entry, so that it is kept for later
+					if weight.saturating_add(payment_weight) > budget {
+						<TransferAgendaCursor<T>>::put(block);
+						return weight
+					}
+					match agenda.next() {
+						Some((id, ())) => Self::execute_scheduled_transfer(id, n),
+						None => break,
+					}
+					weight = weight.saturating_add(payment_weight);
+				}
+
+				block = block.saturating_add(One::one());
+			}
+
+			<TransferAgendaCursor<T>>::put(block);
+			weight
+		}
+
+		/// Makes a payment of the scheduled transfer `id` and schedules the next one
+		fn execute_scheduled_transfer(id: ScheduleId, now: T::BlockNumber) {
+			// the transfer might have been already cancelled
+			let mut schedule = match <ScheduledTransfers<T>>::get(id) {
+				Some(schedule) => schedule,
+				None => return,
+			};
+
+			match Self::transfer_impl(schedule.from.clone(), schedule.to.clone(), schedule.amount) {
+				Ok(()) => {
+					Self::deposit_event(Event::ScheduledTransferExecuted { id });
+					schedule.remaining -= 1;
+					schedule.next = schedule.next.saturating_add(schedule.period);
+				},
+				Err(error) => {
+					Self::deposit_event(Event::ScheduledTransferFailed { id, error });
+					match schedule.on_failure {
+						FailurePolicy::Skip => {
+							schedule.remaining -= 1;
+							schedule.next = schedule.next.saturating_add(schedule.period);
+						},
+						FailurePolicy::Retry => {
+							schedule.next = now.saturating_add(One::one());
+						},
+						FailurePolicy::Cancel => {
+							Self::remove_scheduled_transfer(id, &schedule);
+							Self::deposit_event(Event::ScheduledTransferCancelled { id });
+							return
+						},
+					}
+				},
+			}
+
+			if schedule.remaining == 0 {
+				Self::remove_scheduled_transfer(id, &schedule);
+			} else {
+				<TransferAgenda<T>>::insert(schedule.next, id, ());
+				<ScheduledTransfers<T>>::insert(id, schedule);
+			}
+		}
+
+		fn remove_scheduled_transfer(id: ScheduleId, schedule: &ScheduledTransferOf<T>) {
+			<ScheduledTransfers<T>>::remove(id);
+			<ScheduledTransfersCount<T>>::mutate(&schedule.from, |count| {
+				*count = count.saturating_sub(1)
+			});
+		}
+
+		/// Returns the proposal `id` if it has not expired and `who` is a signatory of its multisig
+		fn signatory_proposal(
+			id: ProposalId,
+			who: &T::AccountId,
+		) -> Result<(ProposalOf<T>, Multisig<T::AccountId>), DispatchError> {
+			let proposal = <Proposals<T>>::get(id).ok_or(Error::<T>::UnknownProposal)?;
+			let multisig =
+				<Multisigs<T>>::get(&proposal.multisig).ok_or(Error::<T>::UnknownMultisig)?;
+			ensure!(multisig.signatories.binary_search(who).is_ok(), Error::<T>::NotSignatory);
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() < proposal.expires_at,
+				Error::<T>::ProposalExpired
+			);
+
+			Ok((proposal, multisig))
+		}
+
+		/// Pays the tokens locked in the escrow out to `beneficiary` and forgets about the escrow
+		fn close_escrow(
+			id: EscrowId,
+			escrow: &EscrowOf<T>,
+			beneficiary: T::AccountId,
+		) -> DispatchResult {
+			Self::transfer_impl(Self::account_id(), beneficiary, escrow.amount)?;
+
+			<Escrows<T>>::remove(id);
+			<AccountEscrows<T>>::remove(&escrow.from, id);
+			<AccountEscrows<T>>::remove(&escrow.to, id);
+
+			Ok(())
+		}
+
+		/// Amount of tokens streamed by the stream up to the block `now`
+		fn streamed_amount(stream: &StreamOf<T>, now: T::BlockNumber) -> U256 {
+			let elapsed = now.clamp(stream.start, stream.stop) - stream.start;
+
+			// can't overflow: it's not greater than the deposit
+			stream.rate_per_block * U256::from(elapsed.saturated_into::<u128>())
+		}
+
+		/// Message to be signed by the owner of an allocation to claim it to `dest`
+		fn claim_message(dest: &T::AccountId) -> Vec<u8> {
+			let mut message = T::ClaimPrefix::get().to_vec();
+			message.extend(dest.encode());
+			message
+		}
+
+		/// Owner of the allocation who has signed its claim to `dest`, if the signature is valid
+		fn claim_owner(
+			dest: &T::AccountId,
+			signature: &ClaimSignatureOf<T>,
+		) -> Option<ClaimOwnerOf<T>> {
+			let message = Self::claim_message(dest);
+
+			match signature {
+				ClaimSignature::Account { owner, signature } => signature
+					.verify(&message[..], owner)
+					.then(|| ClaimOwner::Account(owner.clone())),
+				ClaimSignature::Ethereum(signature) =>
+					eth_recover(signature, T::ClaimPrefix::get(), &dest.encode())
+						.map(ClaimOwner::Ethereum),
+			}
+		}
+
+		fn decode_abi(calldata: &[u8]) -> Result<Erc20Function, Error<T>> {
+			Erc20Function::decode(calldata).map_err(|e| match e {
+				AbiError::UnknownSelector => Error::<T>::UnknownAbiSelector,
+				AbiError::InvalidArguments => Error::<T>::InvalidAbiArguments,
+			})
+		}
+
+		/// Account linked to an address of ABI-encoded calldata
+		fn abi_account(address: EthereumAddress) -> Result<T::AccountId, Error<T>> {
+			<EthereumAccounts<T>>::get(address).ok_or(Error::<T>::UnlinkedEthereumAddress)
+		}
+
+		/// Ethereum address which has signed `payload`, if the signature is valid
+		fn ethereum_signer(
+			payload: &EthereumPayload<T::AccountId>,
+			signature: &EcdsaSignature,
+		) -> Option<EthereumAddress> {
+			eth_recover(signature, T::EthereumPrefix::get(), &payload.encode())
+		}
+
+		/// Makes `call` on behalf of `account`, reverting its changes if it fails
+		fn ethereum_dispatch(account: T::AccountId, call: EthereumCallOf<T>) -> DispatchResult {
+			let origin: OriginFor<T> = RawOrigin::Signed(account).into();
+
+			with_transaction(|| {
+				let result = match call {
+					EthereumCall::Transfer { to, amount } => Self::transfer(origin, to, amount),
+					EthereumCall::Approve { spender, amount } =>
+						Self::approve(origin, spender, amount),
+					EthereumCall::TransferFrom { from, to, amount } =>
+						Self::transfer_from(origin, from, to, amount),
+				};
+
+				if result.is_ok() {
+					TransactionOutcome::Commit(result)
+				} else {
+					TransactionOutcome::Rollback(result)
+				}
+			})
+		}
+
+		/// Whether `call` made by `account` would change nothing: transfers of 0 tokens and
+		/// approvals of the current allowance
+		fn is_no_op(account: &T::AccountId, call: &EthereumCallOf<T>) -> bool {
+			match call {
+				EthereumCall::Transfer { amount, .. } | EthereumCall::TransferFrom { amount, .. } =>
+					amount.is_zero(),
+				EthereumCall::Approve { spender, amount } =>
+					<Allowance<T>>::get(account, spender).unwrap_or_default() == *amount,
+			}
+		}
+
+		/// Validity of the unsigned `ethereum_transact`
+		fn validate_ethereum_transaction(
+			nonce: u64,
+			call: &EthereumCallOf<T>,
+			signature: &EcdsaSignature,
+		) -> TransactionValidity {
+			let payload = EthereumPayload::Transaction { nonce, call: call.clone() };
+			let address =
+				Self::ethereum_signer(&payload, signature).ok_or(InvalidTransaction::BadProof)?;
+			let account = <EthereumAccounts<T>>::get(address)
+				.ok_or(InvalidTransaction::Custom(UNLINKED_ETHEREUM_ADDRESS))?;
+			let next_nonce = <EthereumNonces<T>>::get(address);
+			ensure!(nonce >= next_nonce, InvalidTransaction::Stale);
+
+			// the transaction is free, so an address only gets one into the blocks every
+			// `EthereumTransactionInterval` blocks, and the calls changing nothing are rejected
+			if let Some(last) = <EthereumLastTransactions<T>>::get(address) {
+				ensure!(
+					<frame_system::Pallet<T>>::block_number() >=
+						last.saturating_add(T::EthereumTransactionInterval::get()),
+					InvalidTransaction::Custom(RATE_LIMITED_ETHEREUM_ADDRESS)
+				);
+			}
+			ensure!(!Self::is_no_op(&account, call), InvalidTransaction::Custom(NO_OP_ETHEREUM_CALL));
+
+			// a failing call would fill blocks for nothing as well: the call is made against the
+			// current state and its changes are discarded
+			let succeeds = with_transaction(|| {
+				TransactionOutcome::Rollback(Self::ethereum_dispatch(account, call.clone()).is_ok())
+			});
+			ensure!(succeeds, InvalidTransaction::Custom(FAILING_ETHEREUM_CALL));
+
+			let mut transaction = ValidTransaction::with_tag_prefix("Erc20Ethereum")
+				.priority(T::EthereumTransactionPriority::get())
+				.longevity(T::UnsignedLongevity::get())
+				.and_provides((address, nonce))
+				.propagate(true);
+			if nonce > next_nonce {
+				transaction = transaction.and_requires((address, nonce - 1));
+			}
+			transaction.build()
+		}
+
+		fn deposit_transfer_log(
+			from: Option<&T::AccountId>,
+			to: Option<&T::AccountId>,
+			amount: U256,
+		) {
+			let address = |account: Option<&T::AccountId>| {
+				account
+					.map(Self::ethereum_address)
+					.unwrap_or(EthereumAddress([0; 20]))
+					.to_word()
+			};
+			Self::deposit_log(
+				b"Transfer(address,address,uint256)",
+				[address(from), address(to)],
+				amount,
+			);
+		}
+
+		fn deposit_approval_log(owner: &T::AccountId, spender: &T::AccountId, amount: U256) {
+			Self::deposit_log(
+				b"Approval(address,address,uint256)",
+				[
+					Self::ethereum_address(owner).to_word(),
+					Self::ethereum_address(spender).to_word(),
+				],
+				amount,
+			);
+		}
+
+		/// Deposits an `EthereumLog` of the event `signature`, if enabled
+		fn deposit_log(signature: &[u8], indexed: [H256; 2], amount: U256) {
+			if !T::EthereumLogs::get() {
+				return
+			}
+
+			let mut topics = sp_std::vec![H256(keccak_256(signature))];
+			topics.extend(indexed);
+			let mut data = sp_std::vec![0; 32];
+			amount.to_big_endian(&mut data);
+
+			Self::deposit_event(Event::EthereumLog {
+				address: Self::ethereum_contract_address(),
+				topics,
+				data,
+			});
+		}
+
+		/// Finds an open HTLC which can be unlocked by `preimage`
+		fn find_htlc(preimage: &[u8]) -> Option<(HashLock, HtlcOf<T>)> {
+			HashLock::from_preimage(preimage)
+				.into_iter()
+				.find_map(|hashlock| <Htlcs<T>>::get(hashlock).map(|htlc| (hashlock, htlc)))
+		}
+	}
+
+	// non-dispatchable functions, but other pallets can call them
+	impl<T: Config> Pallet<T> {
+		/// The account holding the tokens locked by the pallet (e.g. in escrows)
+		pub fn account_id() -> T::AccountId {
+			T::PalletId::get().into_account()
+		}
+
+		/// Deterministically derives the multisig account controlled by `threshold` of
+		/// `signatories` (the order of signatories doesn't matter)
+		pub fn multisig_account_id(signatories: &[T::AccountId], threshold: u16) -> T::AccountId {
+			let mut signatories = signatories.to_vec();
+			signatories.sort();
+
+			let entropy = (b"erc20/multisig", signatories, threshold).using_encoded(blake2_256);
+			T::AccountId::decode(&mut TrailingZeroInput::new(entropy.as_ref()))
+				.expect("infinite length input; no invalid inputs for type; qed")
+		}
+
+		/// Ethereum representation of `account`: its linked address if any, otherwise its first 20
+		/// encoded bytes (like the truncated mapping of `AccountId32` used by Frontier)
+		pub fn ethereum_address(account: &T::AccountId) -> EthereumAddress {
+			<EthereumAddresses<T>>::get(account).unwrap_or_else(|| {
+				let mut address = [0; 20];
+				let encoded = account.encode();
+				let len = encoded.len().min(20);
+				address[..len].copy_from_slice(&encoded[..len]);
+				EthereumAddress(address)
+			})
+		}
+
+		/// Address of the token in the `EthereumLog` events, the Ethereum representation of the
+		/// pallet account
+		pub fn ethereum_contract_address() -> EthereumAddress {
+			Self::ethereum_address(&Self::account_id())
+		}
+
+		/// Amount of tokens the recipient of the stream `id` can withdraw right now
+		pub fn stream_withdrawable(id: StreamId) -> Option<U256> {
+			let stream = <Streams<T>>::get(id)?;
+			let now = <frame_system::Pallet<T>>::block_number();
+
+			Some(Self::streamed_amount(&stream, now) - stream.withdrawn)
+		}
+
+		/// Simulates a transfer of `amount` tokens from `from` to `to`, made by `spender` with
+		/// `transfer_from` or by `from` itself with `transfer` if there is no `spender`.
+		/// The state is left untouched, the error the transfer would fail with is returned
+		pub fn simulate_transfer(
+			from: T::AccountId,
+			to: T::AccountId,
+			amount: U256,
+			spender: Option<T::AccountId>,
+		) -> Result<TransferSimulationOf<T>, DispatchError>
+		where
+			<T as frame_system::Config>::Event: TryInto<Event<T>>,
+		{
+			with_transaction(|| {
+				let events_before = <frame_system::Pallet<T>>::events().len();
+
+				let result = match &spender {
+					Some(spender) => Self::transfer_from(
+						RawOrigin::Signed(spender.clone()).into(),
+						from.clone(),
+						to.clone(),
+						amount,
+					),
+					None =>
+						Self::transfer(RawOrigin::Signed(from.clone()).into(), to.clone(), amount),
+				}
+				.map(|_| TransferSimulation {
+					from_balance: <Balance<T>>::get(&from).unwrap_or_default(),
+					to_balance: <Balance<T>>::get(&to).unwrap_or_default(),
+					allowance: spender
+						.as_ref()
+						.map(|spender| <Allowance<T>>::get(&from, spender).unwrap_or_default()),
+					events: <frame_system::Pallet<T>>::events()
+						.into_iter()
+						.skip(events_before)
+						.filter_map(|record| record.event.try_into().ok())
+						.collect(),
+				});
+
+				TransactionOutcome::Rollback(result)
+			})
+		}
+
+		/// Decodes the ABI-encoded calldata of `transfer`, `approve` or `transferFrom` into the
+		/// matching call of the pallet. The addresses must be linked to accounts
+		pub fn abi_call(calldata: &[u8]) -> Result<Call<T>, DispatchError> {
+			Ok(match Self::decode_abi(calldata)? {
+				Erc20Function::Transfer { to, amount } =>
+					Call::transfer { to: Self::abi_account(to)?, amount },
+				Erc20Function::Approve { spender, amount } =>
+					Call::approve { spender: Self::abi_account(spender)?, amount },
+				Erc20Function::TransferFrom { from, to, amount } => Call::transfer_from {
+					from: Self::abi_account(from)?,
+					to: Self::abi_account(to)?,
+					amount,
+				},
+				_ => return Err(Error::<T>::AbiViewFunction.into()),
+			})
+		}
+
+		/// Executes ABI-encoded calldata like `eth_call` does on an ERC20 contract, returning the
+		/// ABI-encoded result. View functions are answered from the state, unlinked addresses
+		/// holding no tokens. The other functions are made by the account linked to `from`, and
+		/// rolled back
+		pub fn eth_call(
+			from: Option<EthereumAddress>,
+			calldata: &[u8],
+		) -> Result<Vec<u8>, DispatchError> {
+			let function = Self::decode_abi(calldata)?;
+
+			Ok(match function {
+				Erc20Function::Name => abi::encode_string(T::Name::get()),
+				Erc20Function::Symbol => abi::encode_string(T::Symbol::get()),
+				Erc20Function::Decimals => abi::encode_uint(T::Decimals::get().into()),
+				Erc20Function::TotalSupply =>
+					abi::encode_uint(<TotalSupply<T>>::get().unwrap_or_default()),
+				Erc20Function::BalanceOf { owner } => abi::encode_uint(
+					<EthereumAccounts<T>>::get(owner)
+						.and_then(<Balance<T>>::get)
+						.unwrap_or_default(),
+				),
+				Erc20Function::Allowance { owner, spender } => abi::encode_uint(
+					<EthereumAccounts<T>>::get(owner)
+						.zip(<EthereumAccounts<T>>::get(spender))
+						.and_then(|(owner, spender)| <Allowance<T>>::get(owner, spender))
+						.unwrap_or_default(),
+				),
+				_ => {
+					let account = from
+						.and_then(<EthereumAccounts<T>>::get)
+						.ok_or(Error::<T>::UnlinkedEthereumAddress)?;
+					let call = Self::abi_call(calldata)?;
+
+					with_transaction(|| {
+						TransactionOutcome::Rollback(
+							call.dispatch_bypass_filter(RawOrigin::Signed(account).into()),
+						)
+					})
+					.map_err(|e| e.error)?;
+
+					abi::encode_bool(true)
+				},
+			})
+		}
+
+		/// Amount of tokens minted when wrapping `native_amount` of the native currency, `None` if
+		/// it has no exact equivalent
+		pub fn wrapped_amount(native_amount: NativeBalanceOf<T>) -> Option<U256> {
+			let native_amount: u128 = native_amount.try_into().ok()?;
+			convert_decimals(native_amount.into(), T::NativeDecimals::get(), T::Decimals::get())
+		}
+
+		/// Amount of the native currency unlocked when unwrapping `amount` tokens, `None` if it
+		/// has no exact equivalent
+		pub fn unwrapped_amount(amount: U256) -> Option<NativeBalanceOf<T>> {
+			let native_amount =
+				convert_decimals(amount, T::Decimals::get(), T::NativeDecimals::get())?;
+			if native_amount > U256::from(u128::MAX) {
+				return None
+			}
+			native_amount.low_u128().try_into().ok()
+		}
+
+		/// Creates `amount` tokens and assigns them to `account`, increasing the total supply.
+		/// Emits a `Mint` event
+		pub fn mint(account: T::AccountId, amount: U256) -> DispatchResult {
+			let total_supply = <TotalSupply<T>>::get().unwrap_or(U256::zero());
+			let balance = <Balance<T>>::get(&account).unwrap_or(U256::zero());
+
+			let total_supply = total_supply.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+			let balance = balance.checked_add(amount).ok_or(Error::<T>::Overflow)?;
+
+			<TotalSupply<T>>::put(total_supply);
+			<Balance<T>>::insert(&account, balance);
+
+			Self::deposit_transfer_log(None, Some(&account), amount);
+			Self::deposit_event(Event::Mint { account, amount });
+
+			Ok(())
+		}
+
+		/// Tops the pallet account up to the existential deposit on top of the wrapped native
+		/// currency, so that unwrapping never reaps it. Does nothing once endowed
+		fn endow_pallet_account() {
+			let account = Self::account_id();
+			let wrapped =
+				Self::unwrapped_amount(<WrappedSupply<T>>::get()).unwrap_or_else(Zero::zero);
+			let required = wrapped.saturating_add(T::NativeCurrency::minimum_balance());
+			let balance = T::NativeCurrency::free_balance(&account);
+
+			if balance < required {
+				let _ = T::NativeCurrency::deposit_creating(&account, required - balance);
+			}
+		}
+
+		/// Removes up to `MaxSweptBitmapWords` words of the claimed bitmap of the swept airdrop
+		/// `id`, which stays in `AirdropsBeingSwept` until the whole bitmap is removed
+		fn remove_claimed_bitmap(id: AirdropId) {
+			let limit = T::MaxSweptBitmapWords::get();
+			match <AirdropClaimedBitmap<T>>::remove_prefix(id, Some(limit)) {
+				KillStorageResult::AllRemoved(_) => <AirdropsBeingSwept<T>>::remove(id),
+				KillStorageResult::SomeRemaining(_) => <AirdropsBeingSwept<T>>::insert(id, ()),
+			}
+		}
+
+		/// Destroys `amount` tokens from `account`, reducing the total supply.
+		/// Emits a `Burn` event
+		pub fn burn(account: T::AccountId, amount: U256) -> DispatchResult {
+			let total_supply = <TotalSupply<T>>::get().unwrap_or(U256::zero());
+			let balance = <Balance<T>>::get(&account).unwrap_or(U256::zero());
+
+			let total_supply = total_supply.checked_sub(amount).ok_or(Error::<T>::Overflow)?;
+			let balance = balance.checked_sub(amount).ok_or(Error::<T>::BurnExceedsBalance)?;
+
+			<TotalSupply<T>>::put(total_supply);
+			<Balance<T>>::insert(&account, balance);
+
+			Self::deposit_transfer_log(Some(&account), None, amount);
 			Self::deposit_event(Event::Burn { account, amount });
 
 			Ok(())
@@ -236,7 +1764,9 @@ pub mod pallet {
 	impl<T: Config> Pallet<T> {
 		/// Moves `amount` tokens from the caller’s account to `to`.
 		/// Emits a `Transfer` event.
-		#[pallet::weight(T::DbWeight::get().reads_writes(2, 3) + 20_000)]
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(2, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
 		pub fn transfer(origin: OriginFor<T>, to: T::AccountId, amount: U256) -> DispatchResult {
 			let owner = ensure_signed(origin)?;
 			Self::transfer_impl(owner, to, amount)
@@ -244,7 +1774,9 @@ pub mod pallet {
 
 		/// Sets `amount` as the allowance of `spender` over the caller’s tokens.
 		/// Emits an `Approval` event.
-		#[pallet::weight(T::DbWeight::get().reads_writes(0, 2) + 20_000)]
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(0, 2) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
 		pub fn approve(
 			origin: OriginFor<T>,
 			spender: T::AccountId,
@@ -259,7 +1791,9 @@ pub mod pallet {
 		///
 		/// Emits a `Transfer` event.
 		/// Might emit `Approval` event (if the approval amount is not infinite)
-		#[pallet::weight(T::DbWeight::get().reads_writes(3, 5) + 20_000)]
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 5) + 20_000 + ethereum_logs_weight::<T>(2)
+		)]
 		pub fn transfer_from(
 			origin: OriginFor<T>,
 			from: T::AccountId,
@@ -284,5 +1818,953 @@ pub mod pallet {
 
 			Ok(())
 		}
+
+		/// Locks `amount` of caller's tokens in an escrow for `to`.
+		/// The tokens are transferred to `to` only when the caller releases them.
+		/// Otherwise they are returned to the caller when `to` refunds them
+		/// or when `expires_at` block is reached.
+		///
+		/// Emits `Transfer` (to the pallet account) and `EscrowCreated` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 7) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		#[transactional]
+		pub fn create_escrow(
+			origin: OriginFor<T>,
+			to: T::AccountId,
+			amount: U256,
+			expires_at: T::BlockNumber,
+		) -> DispatchResult {
+			let from = ensure_signed(origin)?;
+			ensure!(
+				expires_at > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::EscrowExpiryInPast
+			);
+
+			let id = <NextEscrowId<T>>::get();
+			<NextEscrowId<T>>::put(id.checked_add(1).ok_or(Error::<T>::Overflow)?);
+
+			Self::transfer_impl(from.clone(), Self::account_id(), amount)?;
+
+			<Escrows<T>>::insert(
+				id,
+				Escrow { from: from.clone(), to: to.clone(), amount, expires_at },
+			);
+			<AccountEscrows<T>>::insert(&from, id, ());
+			<AccountEscrows<T>>::insert(&to, id, ());
+			// automatic expiry is best-effort: when the block already has too many expiring escrows
+			// this one has to be settled with `claim_expired`
+			let _ = <EscrowExpiries<T>>::try_mutate(expires_at, |queue| queue.try_push(id));
+
+			Self::deposit_event(Event::EscrowCreated { id, from, to, amount, expires_at });
+
+			Ok(())
+		}
+
+		/// Releases the tokens locked in escrow `id` to its recipient.
+		/// Can be called only by the sender of the escrow.
+		///
+		/// Emits `Transfer` and `EscrowReleased` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 5) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn release(origin: OriginFor<T>, id: EscrowId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let escrow = <Escrows<T>>::get(id).ok_or(Error::<T>::UnknownEscrow)?;
+			ensure!(escrow.from == who, Error::<T>::NotEscrowSender);
+
+			Self::close_escrow(id, &escrow, escrow.to.clone())?;
+
+			Self::deposit_event(Event::EscrowReleased { id });
+
+			Ok(())
+		}
+
+		/// Returns the tokens locked in escrow `id` to its sender.
+		/// Can be called only by the recipient of the escrow.
+		///
+		/// Emits `Transfer` and `EscrowRefunded` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 5) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn refund(origin: OriginFor<T>, id: EscrowId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let escrow = <Escrows<T>>::get(id).ok_or(Error::<T>::UnknownEscrow)?;
+			ensure!(escrow.to == who, Error::<T>::NotEscrowRecipient);
+
+			Self::close_escrow(id, &escrow, escrow.from.clone())?;
+
+			Self::deposit_event(Event::EscrowRefunded { id });
+
+			Ok(())
+		}
+
+		/// Returns the tokens locked in an expired escrow `id` to its sender.
+		/// Expired escrows are usually settled automatically,
+		/// this is needed only when that did not happen (e.g. too many escrows expired in one
+		/// block). Can be called by anyone.
+		///
+		/// Emits `Transfer` and `EscrowExpired` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 5) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn claim_expired(origin: OriginFor<T>, id: EscrowId) -> DispatchResult {
+			ensure_signed(origin)?;
+			let escrow = <Escrows<T>>::get(id).ok_or(Error::<T>::UnknownEscrow)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() >= escrow.expires_at,
+				Error::<T>::EscrowNotExpired
+			);
+
+			Self::close_escrow(id, &escrow, escrow.from.clone())?;
+
+			Self::deposit_event(Event::EscrowExpired { id });
+
+			Ok(())
+		}
+
+		/// Locks `amount` of caller's tokens in a hashed time-locked contract.
+		/// The tokens are transferred to `recipient` when someone reveals the preimage of
+		/// `hashlock` before `timelock` block, otherwise they can be refunded to the caller.
+		///
+		/// Emits `Transfer` (to the pallet account) and `HtlcLocked` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn lock_htlc(
+			origin: OriginFor<T>,
+			hashlock: HashLock,
+			recipient: T::AccountId,
+			amount: U256,
+			timelock: T::BlockNumber,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(
+				timelock > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::HtlcTimelockInPast
+			);
+			ensure!(!<Htlcs<T>>::contains_key(hashlock), Error::<T>::HtlcAlreadyExists);
+
+			Self::transfer_impl(sender.clone(), Self::account_id(), amount)?;
+
+			<Htlcs<T>>::insert(
+				hashlock,
+				Htlc { sender: sender.clone(), recipient: recipient.clone(), amount, timelock },
+			);
+
+			Self::deposit_event(Event::HtlcLocked {
+				hashlock,
+				sender,
+				recipient,
+				amount,
+				timelock,
+			});
+
+			Ok(())
+		}
+
+		/// Transfers the tokens locked in the HTLC unlocked by `preimage` to its recipient.
+		/// The preimage can be either blake2-256 or sha2-256 one, depending on the hash lock.
+		/// Can be called by anyone before the timelock block.
+		///
+		/// Emits `Transfer` and `HtlcClaimed` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn claim_htlc(origin: OriginFor<T>, preimage: [u8; 32]) -> DispatchResult {
+			ensure_signed(origin)?;
+			let (hashlock, htlc) = Self::find_htlc(&preimage).ok_or(Error::<T>::UnknownHtlc)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() < htlc.timelock,
+				Error::<T>::HtlcExpired
+			);
+
+			Self::transfer_impl(Self::account_id(), htlc.recipient, htlc.amount)?;
+			<Htlcs<T>>::remove(hashlock);
+
+			Self::deposit_event(Event::HtlcClaimed { hashlock, preimage });
+
+			Ok(())
+		}
+
+		/// Returns the tokens locked in the HTLC to its sender.
+		/// Can be called by anyone starting from the timelock block.
+		///
+		/// Emits `Transfer` and `HtlcRefunded` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn refund_htlc(origin: OriginFor<T>, hashlock: HashLock) -> DispatchResult {
+			ensure_signed(origin)?;
+			let htlc = <Htlcs<T>>::get(hashlock).ok_or(Error::<T>::UnknownHtlc)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() >= htlc.timelock,
+				Error::<T>::HtlcNotExpired
+			);
+
+			Self::transfer_impl(Self::account_id(), htlc.sender, htlc.amount)?;
+			<Htlcs<T>>::remove(hashlock);
+
+			Self::deposit_event(Event::HtlcRefunded { hashlock });
+
+			Ok(())
+		}
+
+		/// Schedules `count` payments of `amount` tokens from the caller to `to`,
+		/// the first one at `start` block and the following ones every `period` blocks.
+		/// The tokens are not locked, each payment is made only if the caller has enough of them
+		/// at the time, otherwise `on_failure` decides what to do.
+		///
+		/// Emits `TransferScheduled` event.
+		/// Each payment emits `Transfer` and `ScheduledTransferExecuted` events
+		/// or `ScheduledTransferFailed` event.
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 4) + 20_000)]
+		pub fn schedule_transfer(
+			origin: OriginFor<T>,
+			to: T::AccountId,
+			amount: U256,
+			start: T::BlockNumber,
+			period: T::BlockNumber,
+			count: u32,
+			on_failure: FailurePolicy,
+		) -> DispatchResult {
+			let from = ensure_signed(origin)?;
+			ensure!(
+				start > <frame_system::Pallet<T>>::block_number() && !period.is_zero() && count > 0,
+				Error::<T>::InvalidSchedule
+			);
+
+			let scheduled = <ScheduledTransfersCount<T>>::get(&from);
+			ensure!(
+				scheduled < T::MaxScheduledTransfers::get(),
+				Error::<T>::TooManyScheduledTransfers
+			);
+
+			let id = <NextScheduleId<T>>::get();
+			<NextScheduleId<T>>::put(id.checked_add(1).ok_or(Error::<T>::Overflow)?);
+
+			<ScheduledTransfersCount<T>>::insert(&from, scheduled + 1);
+			<ScheduledTransfers<T>>::insert(
+				id,
+				ScheduledTransfer {
+					from: from.clone(),
+					to: to.clone(),
+					amount,
+					next: start,
+					period,
+					remaining: count,
+					on_failure,
+				},
+			);
+			<TransferAgenda<T>>::insert(start, id, ());
+
+			Self::deposit_event(Event::TransferScheduled {
+				id,
+				from,
+				to,
+				amount,
+				start,
+				period,
+				count,
+			});
+
+			Ok(())
+		}
+
+		/// Cancels the remaining payments of the scheduled transfer.
+		/// Can be called only by the payer.
+		///
+		/// Emits `ScheduledTransferCancelled` event.
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 3) + 20_000)]
+		pub fn cancel_scheduled_transfer(origin: OriginFor<T>, id: ScheduleId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let schedule =
+				<ScheduledTransfers<T>>::get(id).ok_or(Error::<T>::UnknownScheduledTransfer)?;
+			ensure!(schedule.from == who, Error::<T>::NotScheduledTransferPayer);
+
+			<TransferAgenda<T>>::remove(schedule.next, id);
+			Self::remove_scheduled_transfer(id, &schedule);
+
+			Self::deposit_event(Event::ScheduledTransferCancelled { id });
+
+			Ok(())
+		}
+
+		/// Creates a payment stream of `rate_per_block` tokens per block from the caller to
+		/// `recipient` between `start` and `stop` blocks.
+		/// The whole `deposit` (which must be `rate_per_block * (stop - start)`) is locked upfront.
+		///
+		/// Emits `Transfer` (to the pallet account) and `StreamCreated` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 4) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn create_stream(
+			origin: OriginFor<T>,
+			recipient: T::AccountId,
+			rate_per_block: U256,
+			start: T::BlockNumber,
+			stop: T::BlockNumber,
+			deposit: U256,
+		) -> DispatchResult {
+			let sender = ensure_signed(origin)?;
+			ensure!(
+				start >= <frame_system::Pallet<T>>::block_number() && stop > start,
+				Error::<T>::InvalidStreamSchedule
+			);
+
+			let duration = U256::from((stop - start).saturated_into::<u128>());
+			let expected_deposit =
+				rate_per_block.checked_mul(duration).ok_or(Error::<T>::Overflow)?;
+			ensure!(deposit == expected_deposit, Error::<T>::StreamDepositMismatch);
+
+			let id = <NextStreamId<T>>::get();
+			let next_id = id.checked_add(1).ok_or(Error::<T>::Overflow)?;
+
+			Self::transfer_impl(sender.clone(), Self::account_id(), deposit)?;
+
+			<NextStreamId<T>>::put(next_id);
+			<Streams<T>>::insert(
+				id,
+				Stream {
+					sender: sender.clone(),
+					recipient: recipient.clone(),
+					rate_per_block,
+					start,
+					stop,
+					deposit,
+					withdrawn: U256::zero(),
+				},
+			);
+
+			Self::deposit_event(Event::StreamCreated {
+				id,
+				sender,
+				recipient,
+				rate_per_block,
+				start,
+				stop,
+				deposit,
+			});
+
+			Ok(())
+		}
+
+		/// Transfers all the tokens streamed so far (and not yet withdrawn) to the recipient.
+		/// Can be called only by the recipient of the stream.
+		///
+		/// Emits `Transfer` and `StreamWithdrawn` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn withdraw_from_stream(origin: OriginFor<T>, id: StreamId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut stream = <Streams<T>>::get(id).ok_or(Error::<T>::UnknownStream)?;
+			ensure!(stream.recipient == who, Error::<T>::NotStreamRecipient);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let amount = Self::streamed_amount(&stream, now) - stream.withdrawn;
+
+			Self::transfer_impl(Self::account_id(), who, amount)?;
+
+			stream.withdrawn += amount;
+			if stream.withdrawn == stream.deposit {
+				// the stream is exhausted, no need to keep it around
+				<Streams<T>>::remove(id);
+			} else {
+				<Streams<T>>::insert(id, stream);
+			}
+
+			Self::deposit_event(Event::StreamWithdrawn { id, amount });
+
+			Ok(())
+		}
+
+		/// Cancels the payment stream: the tokens streamed so far (and not yet withdrawn) are
+		/// transferred to the recipient and the rest of the deposit is returned to the sender.
+		/// Can be called either by the sender or by the recipient of the stream.
+		///
+		/// Emits two `Transfer` events and a `StreamCancelled` event.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 4) + 20_000 + ethereum_logs_weight::<T>(2)
+		)]
+		#[transactional]
+		pub fn cancel_stream(origin: OriginFor<T>, id: StreamId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let stream = <Streams<T>>::get(id).ok_or(Error::<T>::UnknownStream)?;
+			ensure!(stream.sender == who || stream.recipient == who, Error::<T>::NotStreamParty);
+
+			let now = <frame_system::Pallet<T>>::block_number();
+			let streamed = Self::streamed_amount(&stream, now);
+			let recipient_amount = streamed - stream.withdrawn;
+			let sender_amount = stream.deposit - streamed;
+
+			Self::transfer_impl(Self::account_id(), stream.recipient, recipient_amount)?;
+			Self::transfer_impl(Self::account_id(), stream.sender, sender_amount)?;
+			<Streams<T>>::remove(id);
+
+			Self::deposit_event(Event::StreamCancelled { id, recipient_amount, sender_amount });
+
+			Ok(())
+		}
+
+		/// Creates a multisig account controlled by `threshold` of `signatories`.
+		/// The account is derived deterministically with `multisig_account_id`,
+		/// tokens can be sent to it before it's created.
+		///
+		/// Emits `MultisigCreated` event.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(1, 1) + 20_000 +
+				signatories_weight(signatories.len() as u64)
+		)]
+		pub fn create_multisig_account(
+			origin: OriginFor<T>,
+			signatories: Vec<T::AccountId>,
+			threshold: u16,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			ensure!(
+				!signatories.is_empty() && signatories.len() <= T::MaxSignatories::get() as usize,
+				Error::<T>::InvalidSignatoriesCount
+			);
+			ensure!(
+				threshold > 0 && threshold as usize <= signatories.len(),
+				Error::<T>::InvalidThreshold
+			);
+
+			let mut signatories = signatories;
+			signatories.sort();
+			ensure!(
+				signatories.windows(2).all(|pair| pair[0] != pair[1]),
+				Error::<T>::DuplicateSignatories
+			);
+
+			let account = Self::multisig_account_id(&signatories, threshold);
+			ensure!(!<Multisigs<T>>::contains_key(&account), Error::<T>::MultisigAlreadyExists);
+
+			<Multisigs<T>>::insert(
+				&account,
+				Multisig { signatories: signatories.clone(), threshold },
+			);
+
+			Self::deposit_event(Event::MultisigCreated { account, signatories, threshold });
+
+			Ok(())
+		}
+
+		/// Proposes to transfer `amount` tokens from `multisig` account to `to`.
+		/// The proposal is approved by the caller, who must be a signatory of the multisig account.
+		/// It expires after `ProposalLifetime` blocks.
+		///
+		/// Emits `ProposalCreated` and `ProposalApproved` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(2, 2) + 20_000 +
+				signatories_weight(T::MaxSignatories::get().into())
+		)]
+		pub fn propose_transfer(
+			origin: OriginFor<T>,
+			multisig: T::AccountId,
+			to: T::AccountId,
+			amount: U256,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let account = <Multisigs<T>>::get(&multisig).ok_or(Error::<T>::UnknownMultisig)?;
+			ensure!(account.signatories.binary_search(&who).is_ok(), Error::<T>::NotSignatory);
+
+			let id = <NextProposalId<T>>::get();
+			<NextProposalId<T>>::put(id.checked_add(1).ok_or(Error::<T>::Overflow)?);
+
+			let expires_at = <frame_system::Pallet<T>>::block_number()
+				.saturating_add(T::ProposalLifetime::get());
+			<Proposals<T>>::insert(
+				id,
+				Proposal {
+					multisig: multisig.clone(),
+					to: to.clone(),
+					amount,
+					approvals: sp_std::vec![who.clone()],
+					expires_at,
+				},
+			);
+
+			Self::deposit_event(Event::ProposalCreated { id, multisig, to, amount, expires_at });
+			Self::deposit_event(Event::ProposalApproved { id, signatory: who, approvals: 1 });
+
+			Ok(())
+		}
+
+		/// Approves the transfer proposal `id`.
+		/// Can be called only by a signatory of the multisig account before the proposal expires.
+		///
+		/// Emits `ProposalApproved` event.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(2, 1) + 20_000 +
+				signatories_weight(T::MaxSignatories::get().into())
+		)]
+		pub fn approve_proposal(origin: OriginFor<T>, id: ProposalId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (mut proposal, _) = Self::signatory_proposal(id, &who)?;
+			ensure!(!proposal.approvals.contains(&who), Error::<T>::AlreadyApproved);
+
+			proposal.approvals.push(who.clone());
+			let approvals = proposal.approvals.len() as u32;
+			<Proposals<T>>::insert(id, proposal);
+
+			Self::deposit_event(Event::ProposalApproved { id, signatory: who, approvals });
+
+			Ok(())
+		}
+
+		/// Executes the transfer proposal `id` once it has at least `threshold` approvals.
+		/// Can be called only by a signatory of the multisig account before the proposal expires.
+		///
+		/// Emits `Transfer` and `ProposalExecuted` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 3) + 20_000 +
+				signatories_weight(T::MaxSignatories::get().into()) +
+				ethereum_logs_weight::<T>(1)
+		)]
+		pub fn execute_proposal(origin: OriginFor<T>, id: ProposalId) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let (proposal, multisig) = Self::signatory_proposal(id, &who)?;
+			ensure!(
+				proposal.approvals.len() >= multisig.threshold as usize,
+				Error::<T>::NotEnoughApprovals
+			);
+
+			Self::transfer_impl(proposal.multisig, proposal.to, proposal.amount)?;
+			<Proposals<T>>::remove(id);
+
+			Self::deposit_event(Event::ProposalExecuted { id });
+
+			Ok(())
+		}
+
+		/// Removes the expired transfer proposal `id`. Can be called by anyone.
+		///
+		/// Emits `ProposalExpired` event.
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 1) + 20_000)]
+		pub fn remove_expired_proposal(origin: OriginFor<T>, id: ProposalId) -> DispatchResult {
+			ensure_signed(origin)?;
+			let proposal = <Proposals<T>>::get(id).ok_or(Error::<T>::UnknownProposal)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() >= proposal.expires_at,
+				Error::<T>::ProposalNotExpired
+			);
+
+			<Proposals<T>>::remove(id);
+
+			Self::deposit_event(Event::ProposalExpired { id });
+
+			Ok(())
+		}
+
+		/// Claims the airdrop allocation of the signer of `signature` to `dest`, minting its
+		/// tokens. Submitted as an unsigned (and feeless) transaction, so that `dest` doesn't
+		/// need any native tokens. `signature` should sign `ClaimPrefix` followed by the
+		/// SCALE-encoded `dest`, hex-encoded for Ethereum addresses.
+		///
+		/// Emits `Mint` and `Claimed` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(3, 3) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn claim(
+			origin: OriginFor<T>,
+			dest: T::AccountId,
+			signature: ClaimSignatureOf<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let owner =
+				Self::claim_owner(&dest, &signature).ok_or(Error::<T>::InvalidClaimSignature)?;
+			let amount = <Claims<T>>::get(&owner).ok_or(Error::<T>::NoClaim)?;
+
+			Self::mint(dest.clone(), amount)?;
+			<Claims<T>>::remove(&owner);
+
+			Self::deposit_event(Event::Claimed { owner, dest, amount });
+
+			Ok(())
+		}
+
+		/// Creates an airdrop of `total` tokens to the leaves of the Merkle tree with root
+		/// `merkle_root`, which can be claimed until `expires_at` block. The tokens are minted
+		/// when called by `AirdropOrigin`, otherwise they are locked from the caller's account.
+		///
+		/// Emits `Transfer` (or `Mint`) and `AirdropCreated` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 4) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn create_airdrop(
+			origin: OriginFor<T>,
+			merkle_root: MerkleRoot,
+			total: U256,
+			expires_at: T::BlockNumber,
+		) -> DispatchResult {
+			let funder = match T::AirdropOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+			ensure!(
+				expires_at > <frame_system::Pallet<T>>::block_number(),
+				Error::<T>::AirdropExpiryInPast
+			);
+
+			let id = <NextAirdropId<T>>::get();
+			let next_id = id.checked_add(1).ok_or(Error::<T>::Overflow)?;
+
+			match &funder {
+				Some(funder) => Self::transfer_impl(funder.clone(), Self::account_id(), total)?,
+				None => Self::mint(Self::account_id(), total)?,
+			}
+
+			<NextAirdropId<T>>::put(next_id);
+			<Airdrops<T>>::insert(
+				id,
+				Airdrop {
+					funder: funder.clone(),
+					merkle_root,
+					total,
+					remaining: total,
+					expires_at,
+				},
+			);
+
+			Self::deposit_event(Event::AirdropCreated {
+				id,
+				funder,
+				merkle_root,
+				total,
+				expires_at,
+			});
+
+			Ok(())
+		}
+
+		/// Claims the leaf `index` of airdrop `id`, granting `amount` tokens to `account`, with
+		/// the Merkle `proof` of the leaf. Can be called by anyone before the airdrop expires.
+		///
+		/// Emits `Transfer` and `AirdropClaimed` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(4, 4) + 20_000 + 5_000 * proof.len() as Weight +
+			ethereum_logs_weight::<T>(1)
+		)]
+		pub fn claim_airdrop(
+			origin: OriginFor<T>,
+			id: AirdropId,
+			index: u32,
+			account: T::AccountId,
+			amount: U256,
+			proof: Vec<H256>,
+		) -> DispatchResult {
+			ensure_signed(origin)?;
+			let mut airdrop = <Airdrops<T>>::get(id).ok_or(Error::<T>::UnknownAirdrop)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() < airdrop.expires_at,
+				Error::<T>::AirdropExpired
+			);
+
+			let (word, bit) = (index / 128, 1u128 << (index % 128));
+			let claimed = <AirdropClaimedBitmap<T>>::get(id, word);
+			ensure!(claimed & bit == 0, Error::<T>::AirdropAlreadyClaimed);
+
+			let leaf = airdrop.merkle_root.leaf(index, &account, amount);
+			ensure!(airdrop.merkle_root.verify(leaf, &proof), Error::<T>::InvalidMerkleProof);
+
+			// a tree granting more than the total can't drain the tokens locked by the pallet
+			airdrop.remaining =
+				airdrop.remaining.checked_sub(amount).ok_or(Error::<T>::AirdropExhausted)?;
+
+			Self::transfer_impl(Self::account_id(), account.clone(), amount)?;
+			<AirdropClaimedBitmap<T>>::insert(id, word, claimed | bit);
+			<Airdrops<T>>::insert(id, airdrop);
+
+			Self::deposit_event(Event::AirdropClaimed { id, index, account, amount });
+
+			Ok(())
+		}
+
+		/// Removes the expired airdrop `id`, returning its unclaimed tokens to the funder (or
+		/// burning them if they were minted). Can be called by anyone starting from the expiry
+		/// block.
+		///
+		/// At most `MaxSweptBitmapWords` words of the claimed bitmap are removed at once: while
+		/// the airdrop is in `AirdropsBeingSwept`, calling it again removes the next ones.
+		///
+		/// Emits `Transfer` (or `Burn`) and `AirdropSwept` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(5, 6 + T::MaxSweptBitmapWords::get() as Weight) +
+				20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		pub fn sweep_airdrop(origin: OriginFor<T>, id: AirdropId) -> DispatchResult {
+			ensure_signed(origin)?;
+			if <AirdropsBeingSwept<T>>::contains_key(id) {
+				Self::remove_claimed_bitmap(id);
+				return Ok(())
+			}
+			let airdrop = <Airdrops<T>>::get(id).ok_or(Error::<T>::UnknownAirdrop)?;
+			ensure!(
+				<frame_system::Pallet<T>>::block_number() >= airdrop.expires_at,
+				Error::<T>::AirdropNotExpired
+			);
+
+			match airdrop.funder {
+				Some(funder) => Self::transfer_impl(Self::account_id(), funder, airdrop.remaining)?,
+				None => Self::burn(Self::account_id(), airdrop.remaining)?,
+			}
+
+			<Airdrops<T>>::remove(id);
+			Self::remove_claimed_bitmap(id);
+
+			Self::deposit_event(Event::AirdropSwept { id, amount: airdrop.remaining });
+
+			Ok(())
+		}
+
+		/// Links the Ethereum address which has signed `EthereumPayload::Link` of the caller to
+		/// the caller, so that it can make transactions with `ethereum_transact`.
+		///
+		/// Emits `EthereumAddressLinked` event.
+		#[pallet::weight(T::DbWeight::get().reads_writes(2, 2) + 20_000)]
+		pub fn link_ethereum_address(
+			origin: OriginFor<T>,
+			signature: EcdsaSignature,
+		) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let address = Self::ethereum_signer(
+				&EthereumPayload::Link { account: account.clone() },
+				&signature,
+			)
+			.ok_or(Error::<T>::InvalidEthereumSignature)?;
+			ensure!(
+				!<EthereumAccounts<T>>::contains_key(address),
+				Error::<T>::EthereumAddressAlreadyLinked
+			);
+			ensure!(
+				!<EthereumAddresses<T>>::contains_key(&account),
+				Error::<T>::AccountAlreadyLinked
+			);
+
+			<EthereumAccounts<T>>::insert(address, &account);
+			<EthereumAddresses<T>>::insert(&account, address);
+
+			Self::deposit_event(Event::EthereumAddressLinked { account, address });
+
+			Ok(())
+		}
+
+		/// Unlinks the Ethereum address linked to the caller.
+		///
+		/// Emits `EthereumAddressUnlinked` event.
+		#[pallet::weight(T::DbWeight::get().reads_writes(1, 2) + 20_000)]
+		pub fn unlink_ethereum_address(origin: OriginFor<T>) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let address =
+				<EthereumAddresses<T>>::take(&account).ok_or(Error::<T>::AccountNotLinked)?;
+			<EthereumAccounts<T>>::remove(address);
+
+			Self::deposit_event(Event::EthereumAddressUnlinked { account, address });
+
+			Ok(())
+		}
+
+		/// Makes `call` on behalf of the account linked to the Ethereum address which has signed
+		/// `EthereumPayload::Transaction { nonce, call }`. Submitted as an unsigned (and feeless)
+		/// transaction, the nonce preventing replays. Transactions whose call would fail or change
+		/// nothing, or sent less than `EthereumTransactionInterval` blocks after the previous one
+		/// of the address, are rejected by `validate_unsigned`, but the nonce is used anyway if
+		/// the call fails.
+		///
+		/// Emits the events of the call and `EthereumTransactionExecuted` event.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(5, 7) + 20_000 + ethereum_logs_weight::<T>(2)
+		)]
+		pub fn ethereum_transact(
+			origin: OriginFor<T>,
+			nonce: u64,
+			call: EthereumCallOf<T>,
+			signature: EcdsaSignature,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			let payload = EthereumPayload::Transaction { nonce, call: call.clone() };
+			let address = Self::ethereum_signer(&payload, &signature)
+				.ok_or(Error::<T>::InvalidEthereumSignature)?;
+			let account =
+				<EthereumAccounts<T>>::get(address).ok_or(Error::<T>::UnlinkedEthereumAddress)?;
+			ensure!(nonce == <EthereumNonces<T>>::get(address), Error::<T>::InvalidEthereumNonce);
+
+			<EthereumNonces<T>>::insert(address, nonce + 1);
+			<EthereumLastTransactions<T>>::insert(
+				address,
+				<frame_system::Pallet<T>>::block_number(),
+			);
+			let result = Self::ethereum_dispatch(account, call);
+
+			Self::deposit_event(Event::EthereumTransactionExecuted { address, nonce, result });
+
+			Ok(())
+		}
+
+		/// Locks `native_amount` of the caller's native currency in the pallet account and mints
+		/// the equivalent tokens (scaled from `NativeDecimals` to `Decimals`) to the caller.
+		///
+		/// Emits `Mint` and `Wrapped` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(5, 5) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		#[transactional]
+		pub fn wrap(origin: OriginFor<T>, native_amount: NativeBalanceOf<T>) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let amount =
+				Self::wrapped_amount(native_amount).ok_or(Error::<T>::InexactWrapAmount)?;
+			let wrapped_supply =
+				<WrappedSupply<T>>::get().checked_add(amount).ok_or(Error::<T>::Overflow)?;
+
+			T::NativeCurrency::transfer(
+				&account,
+				&Self::account_id(),
+				native_amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			Self::mint(account.clone(), amount)?;
+			<WrappedSupply<T>>::put(wrapped_supply);
+
+			Self::deposit_event(Event::Wrapped { account, native_amount, amount });
+
+			Ok(())
+		}
+
+		/// Burns `amount` of the caller's tokens and unlocks the equivalent native currency
+		/// (scaled from `Decimals` to `NativeDecimals`) to the caller. At most the wrapped supply
+		/// can be unwrapped.
+		///
+		/// Emits `Burn` and `Unwrapped` events.
+		#[pallet::weight(
+			T::DbWeight::get().reads_writes(5, 5) + 20_000 + ethereum_logs_weight::<T>(1)
+		)]
+		#[transactional]
+		pub fn unwrap(origin: OriginFor<T>, amount: U256) -> DispatchResult {
+			let account = ensure_signed(origin)?;
+			let native_amount =
+				Self::unwrapped_amount(amount).ok_or(Error::<T>::InexactWrapAmount)?;
+			let wrapped_supply = <WrappedSupply<T>>::get()
+				.checked_sub(amount)
+				.ok_or(Error::<T>::InsufficientWrappedSupply)?;
+
+			Self::burn(account.clone(), amount)?;
+			// the existential deposit endowed at genesis (or by `on_runtime_upgrade`) is never
+			// unwrapped, so the pallet account can't be reaped
+			T::NativeCurrency::transfer(
+				&Self::account_id(),
+				&account,
+				native_amount,
+				ExistenceRequirement::KeepAlive,
+			)?;
+			<WrappedSupply<T>>::put(wrapped_supply);
+
+			Self::deposit_event(Event::Unwrapped { account, native_amount, amount });
+
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		/// Claims are valid if signed by the owner of an allocation which wasn't claimed yet. The
+		/// owner is the tag the transaction provides, so that an allocation can be claimed only
+		/// once.
+		///
+		/// Ethereum transactions are valid if signed by a linked address, with a nonce which
+		/// wasn't used yet, at least `EthereumTransactionInterval` blocks after the previous
+		/// transaction of the address, and if their call would succeed and change something. Like
+		/// in Ethereum, they provide their `(address, nonce)` and require the previous nonce of the
+		/// address, unless it is the next one to be executed. As `pre_dispatch` checks the same, a
+		/// transaction whose call fails when its turn comes is not included in the block
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let (dest, signature) = match call {
+				Call::claim { dest, signature } => (dest, signature),
+				Call::ethereum_transact { nonce, call, signature } =>
+					return Self::validate_ethereum_transaction(*nonce, call, signature),
+				_ => return InvalidTransaction::Call.into(),
+			};
+
+			let owner = Self::claim_owner(dest, signature).ok_or(InvalidTransaction::BadProof)?;
+			ensure!(<Claims<T>>::contains_key(&owner), InvalidTransaction::Custom(NO_CLAIM));
+
+			ValidTransaction::with_tag_prefix("Erc20Claim")
+				.priority(T::UnsignedPriority::get())
+				.longevity(T::UnsignedLongevity::get())
+				.and_provides(owner)
+				.propagate(true)
+				.build()
+		}
+	}
+}
+
+/// Ethereum address which has signed `prefix` followed by the hex-encoded `data` with `eth_sign`
+/// (which prefixes the message with `"\x19Ethereum Signed Message:\n" + length`)
+fn eth_recover(signature: &EcdsaSignature, prefix: &[u8], data: &[u8]) -> Option<EthereumAddress> {
+	let payload = hex_encode(data);
+
+	let mut message = b"\x19Ethereum Signed Message:\n".to_vec();
+	message.extend(decimal_encode(prefix.len() + payload.len()));
+	message.extend(prefix);
+	message.extend(payload);
+
+	let public =
+		sp_io::crypto::secp256k1_ecdsa_recover(&signature.0, &sp_io::hashing::keccak_256(&message))
+			.ok()?;
+	let mut address = [0; 20];
+	address.copy_from_slice(&sp_io::hashing::keccak_256(&public)[12..]);
+	Some(EthereumAddress(address))
+}
+
+/// Weight of mirroring `logs` events as `EthereumLog` events: each one reads the Ethereum
+/// addresses of (at most) two accounts and of the pallet account, and deposits one more event.
+/// Mints and burns are counted like transfers.
+pub fn ethereum_logs_weight<T: Config>(logs: u64) -> Weight {
+	T::DbWeight::get().reads_writes(3 * logs, logs)
+}
+
+/// Weight of going through `signatories` signatories of a multisig account, or approvals of one
+/// of its proposals: the calls which don't know their number (e.g. `approve_proposal`) count
+/// `MaxSignatories` of them.
+pub fn signatories_weight(signatories: u64) -> Weight {
+	10_000 * signatories
+}
+
+/// Converts `amount` with `from` decimals to `to` decimals, `None` if the result is not a whole
+/// number or overflows
+pub fn convert_decimals(amount: U256, from: u8, to: u8) -> Option<U256> {
+	let factor = |exponent: u8| U256::from(10).checked_pow(exponent.into());
+
+	if to >= from {
+		amount.checked_mul(factor(to - from)?)
+	} else {
+		let factor = factor(from - to)?;
+		(amount % factor).is_zero().then(|| amount / factor)
+	}
+}
+
+/// Lowercase hex encoding of `bytes`, without a `0x` prefix
+fn hex_encode(bytes: &[u8]) -> Vec<u8> {
+	const DIGITS: &[u8; 16] = b"0123456789abcdef";
+	bytes
+		.iter()
+		.flat_map(|byte| [DIGITS[(byte >> 4) as usize], DIGITS[(byte & 0xf) as usize]])
+		.collect()
+}
+
+/// ASCII decimal representation of `n` (`ToString` isn't available without `std`)
+fn decimal_encode(mut n: usize) -> Vec<u8> {
+	let mut digits = Vec::new();
+	loop {
+		digits.push(b'0' + (n % 10) as u8);
+		n /= 10;
+		if n == 0 {
+			break
+		}
 	}
+	digits.reverse();
+	digits
 }
@@ -1,12 +1,15 @@
 use crate as pallet_erc20;
+use crate::test_utils::ExtBuilder;
 use frame_support::parameter_types;
-use frame_support::traits::{ConstU16, ConstU64, GenesisBuild};
+use frame_support::traits::{ConstU16, ConstU32, ConstU64, ConstU8, Get};
+use frame_support::PalletId;
 use frame_system as system;
 use sp_core::{H256, U256};
 use sp_runtime::{
-	testing::Header,
+	testing::{Header, TestSignature, UintAuthorityId},
 	traits::{BlakeTwo256, IdentityLookup},
 };
+use std::cell::Cell;
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -19,7 +22,8 @@ frame_support::construct_runtime!(
 		UncheckedExtrinsic = UncheckedExtrinsic,
 	{
 		System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
-		Erc20: pallet_erc20::{Pallet, Call, Config<T>, Storage, Event<T>},
+		Balances: pallet_balances::{Pallet, Call, Config<T>, Storage, Event<T>},
+		Erc20: pallet_erc20::{Pallet, Call, Config<T>, Storage, Event<T>, ValidateUnsigned},
 	}
 );
 
@@ -41,7 +45,7 @@ impl system::Config for Test {
 	type DbWeight = ();
 	type Version = ();
 	type PalletInfo = PalletInfo;
-	type AccountData = ();
+	type AccountData = pallet_balances::AccountData<u64>;
 	type OnNewAccount = ();
 	type OnKilledAccount = ();
 	type SystemWeightInfo = ();
@@ -50,10 +54,45 @@ impl system::Config for Test {
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
 }
 
+impl pallet_balances::Config for Test {
+	type MaxLocks = ();
+	type MaxReserves = ();
+	type ReserveIdentifier = [u8; 8];
+	type Balance = u64;
+	type Event = Event;
+	type DustRemoval = ();
+	type ExistentialDeposit = ConstU64<5>;
+	type AccountStore = System;
+	type WeightInfo = ();
+}
+
 parameter_types! {
 	pub const Name: &'static str = "Meme Coin";
 	pub const Symbol: &'static str = "MEM";
 	pub const Decimals: u8 = 18;
+	pub const Erc20PalletId: PalletId = PalletId(*b"py/erc20");
+	pub const ClaimPrefix: &'static [u8] = b"Pay MEM to the test account:";
+	pub const EthereumPrefix: &'static [u8] = b"Test MEM transaction:";
+}
+
+thread_local! {
+	static ETHEREUM_LOGS: Cell<bool> = Cell::new(false);
+}
+
+/// Whether the events are mirrored as Ethereum logs, off unless a test enables it so that the
+/// expected events stay short
+pub struct EthereumLogs;
+
+impl EthereumLogs {
+	pub fn set(enabled: bool) {
+		ETHEREUM_LOGS.with(|logs| logs.set(enabled));
+	}
+}
+
+impl Get<bool> for EthereumLogs {
+	fn get() -> bool {
+		ETHEREUM_LOGS.with(Cell::get)
+	}
 }
 
 impl pallet_erc20::Config for Test {
@@ -61,22 +100,36 @@ impl pallet_erc20::Config for Test {
 	type Name = Name;
 	type Symbol = Symbol;
 	type Decimals = Decimals;
+	type PalletId = Erc20PalletId;
+	type MaxEscrowExpiries = ConstU32<2>;
+	type MaxScheduledTransfers = ConstU32<2>;
+	// enough for two scheduled payments per block
+	type ScheduledTransfersWeight = ConstU64<50_000>;
+	type MaxSignatories = ConstU32<3>;
+	type ProposalLifetime = ConstU64<10>;
+	// a test signature is valid if made by the account id over the exact message
+	type Signature = TestSignature;
+	type Signer = UintAuthorityId;
+	type ClaimPrefix = ClaimPrefix;
+	type UnsignedPriority = ConstU64<100>;
+	type UnsignedLongevity = ConstU64<64>;
+	type AirdropOrigin = system::EnsureRoot<u64>;
+	type MaxSweptBitmapWords = ConstU32<2>;
+	type EthereumPrefix = EthereumPrefix;
+	type EthereumTransactionPriority = ConstU64<10>;
+	type EthereumTransactionInterval = ConstU64<2>;
+	type EthereumLogs = EthereumLogs;
+	type NativeCurrency = Balances;
+	// a native unit is worth a million of the smallest token units
+	type NativeDecimals = ConstU8<12>;
 }
 
 // Build genesis storage according to the mock runtime.
 pub fn new_test_ext() -> sp_io::TestExternalities {
-	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
-
 	// due to account 3 having U256::max_value() of tokens the total_supply is actually incorrect
 	// but we don't care for the purpose of testing =)
-	pallet_erc20::GenesisConfig::<Test> {
-		total_supply: U256::from(200),
-		balances: vec![(1, U256::from(110)), (2, U256::from(90)), (3, U256::max_value())],
-	}
-	.assimilate_storage(&mut t)
-	.unwrap();
-
-	let mut ext = sp_io::TestExternalities::new(t);
-	ext.execute_with(|| System::set_block_number(1));
-	ext
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, U256::from(110)), (2, U256::from(90)), (3, U256::max_value())])
+		.with_supply(U256::from(200))
+		.build()
 }
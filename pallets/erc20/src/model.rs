@@ -0,0 +1,309 @@
+//! Reference model of the token operations, which the property tests and the fuzz target run
+//! against the pallet, and on which the OpenZeppelin model of `differential.rs` is built.
+//!
+//! Every operation is applied to both the model and the mock runtime, after which the outcome, the
+//! emitted events and the storage must agree, the total supply must match the sum of the balances
+//! and no allowance may have grown other than through an approval.
+
+use crate::{
+	mock::*,
+	test_utils::{erc20_events, ExtBuilder},
+	Event as Erc20Event,
+};
+use sp_core::U256;
+use sp_runtime::DispatchResult;
+use std::collections::BTreeMap;
+
+/// Number of accounts the operations are performed by, account indices are taken modulo it
+pub const ACCOUNTS: u8 = 4;
+
+/// Token operation, with small amounts so that both the successful and the failing paths are taken
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum Op {
+	/// `transfer` signed by `from`
+	Transfer { from: u8, to: u8, amount: u8 },
+	/// `approve` signed by `owner`
+	Approve { owner: u8, spender: u8, amount: u8 },
+	/// `approve` of an infinite allowance, signed by `owner`
+	ApproveUnlimited { owner: u8, spender: u8 },
+	/// `transfer_from` signed by `spender`
+	TransferFrom { spender: u8, from: u8, to: u8, amount: u8 },
+	/// `mint` to `account`
+	Mint { account: u8, amount: u8 },
+	/// `burn` from `account`
+	Burn { account: u8, amount: u8 },
+}
+
+/// Reason why an operation fails, named after the errors of the pallet
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+	InsufficientFunds,
+	InsufficientAllowance,
+	BurnExceedsBalance,
+	Overflow,
+}
+
+/// Arithmetic of the additions to a balance and of the subtractions from the total supply, which
+/// can't overflow as long as the balances add up to the total supply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Arithmetic {
+	/// Fails with `Overflow`, like the pallet
+	Checked,
+	/// Wraps around, like the `unchecked` blocks of Solidity
+	Unchecked,
+}
+
+impl Default for Arithmetic {
+	fn default() -> Self {
+		Self::Checked
+	}
+}
+
+/// Expected state of the pallet
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+	total_supply: U256,
+	balances: BTreeMap<u64, U256>,
+	allowances: BTreeMap<(u64, u64), U256>,
+	arithmetic: Arithmetic,
+}
+
+impl Model {
+	/// Model of the genesis state in which account `i` owns `balances[i]` tokens
+	pub fn new(balances: &[u8]) -> Self {
+		let balances = balances
+			.iter()
+			.take(ACCOUNTS as usize)
+			.enumerate()
+			.map(|(account, balance)| (account as u64, U256::from(*balance)))
+			.collect::<BTreeMap<_, _>>();
+
+		let total_supply = balances.values().fold(U256::zero(), |total, balance| total + *balance);
+
+		Self { total_supply, balances, ..Default::default() }
+	}
+
+	/// Model of the given state, which (unlike the genesis state of the pallet) doesn't have to be
+	/// consistent
+	pub fn with_state(
+		total_supply: U256,
+		balances: impl IntoIterator<Item = (u64, U256)>,
+		arithmetic: Arithmetic,
+	) -> Self {
+		Self {
+			total_supply,
+			balances: balances.into_iter().collect(),
+			allowances: BTreeMap::new(),
+			arithmetic,
+		}
+	}
+
+	/// Applies `op`, returning the events it must emit, or `None` (and leaving the model untouched)
+	/// if it must fail
+	pub fn apply(&mut self, op: &Op) -> Option<Vec<Erc20Event<Test>>> {
+		let events = match *op {
+			Op::Transfer { from, to, amount } =>
+				self.transfer(account(from), account(to), amount.into()),
+			Op::Approve { owner, spender, amount } =>
+				Ok(self.approve(account(owner), account(spender), amount.into())),
+			Op::ApproveUnlimited { owner, spender } =>
+				Ok(self.approve(account(owner), account(spender), U256::max_value())),
+			Op::TransferFrom { spender, from, to, amount } =>
+				self.transfer_from(account(spender), account(from), account(to), amount.into()),
+			Op::Mint { account: to, amount } => self.mint(account(to), amount.into()),
+			Op::Burn { account: from, amount } => self.burn(account(from), amount.into()),
+		};
+
+		events.ok()
+	}
+
+	pub fn total_supply(&self) -> U256 {
+		self.total_supply
+	}
+
+	pub fn balance(&self, account: u64) -> U256 {
+		self.balances.get(&account).copied().unwrap_or_default()
+	}
+
+	pub fn allowance(&self, owner: u64, spender: u64) -> U256 {
+		self.allowances.get(&(owner, spender)).copied().unwrap_or_default()
+	}
+
+	/// Moves `amount` tokens from `from` to `to`
+	pub fn transfer(
+		&mut self,
+		from: u64,
+		to: u64,
+		amount: U256,
+	) -> Result<Vec<Erc20Event<Test>>, Failure> {
+		let from_balance =
+			self.balance(from).checked_sub(amount).ok_or(Failure::InsufficientFunds)?;
+		if from != to {
+			let to_balance = self.add(self.balance(to), amount)?;
+			self.balances.insert(from, from_balance);
+			self.balances.insert(to, to_balance);
+		}
+
+		Ok(vec![Erc20Event::Transfer { from, to, amount }])
+	}
+
+	/// Sets the allowance of `spender` over the tokens of `owner` to `amount`
+	pub fn approve(&mut self, owner: u64, spender: u64, amount: U256) -> Vec<Erc20Event<Test>> {
+		self.allowances.insert((owner, spender), amount);
+		vec![Erc20Event::Approval { owner, spender, amount }]
+	}
+
+	/// Allowance of `spender` over the tokens of `owner` once `amount` of it is spent, `None` for
+	/// an infinite allowance which is never spent
+	pub fn spent_allowance(
+		&self,
+		owner: u64,
+		spender: u64,
+		amount: U256,
+	) -> Result<Option<U256>, Failure> {
+		let allowance = self.allowance(owner, spender);
+		if allowance == U256::max_value() {
+			return Ok(None)
+		}
+
+		allowance.checked_sub(amount).map(Some).ok_or(Failure::InsufficientAllowance)
+	}
+
+	/// Moves `amount` tokens from `from` to `to` on behalf of `spender`, the transfer coming
+	/// before the approval of the spent allowance like in the pallet
+	pub fn transfer_from(
+		&mut self,
+		spender: u64,
+		from: u64,
+		to: u64,
+		amount: U256,
+	) -> Result<Vec<Erc20Event<Test>>, Failure> {
+		let new_allowance = self.spent_allowance(from, spender, amount)?;
+		let mut events = self.transfer(from, to, amount)?;
+
+		if let Some(new_allowance) = new_allowance {
+			events.extend(self.approve(from, spender, new_allowance));
+		}
+		Ok(events)
+	}
+
+	/// Creates `amount` tokens owned by `account`
+	pub fn mint(&mut self, account: u64, amount: U256) -> Result<Vec<Erc20Event<Test>>, Failure> {
+		let total_supply = self.total_supply.checked_add(amount).ok_or(Failure::Overflow)?;
+		let balance = self.add(self.balance(account), amount)?;
+		self.total_supply = total_supply;
+		self.balances.insert(account, balance);
+
+		Ok(vec![Erc20Event::Mint { account, amount }])
+	}
+
+	/// Destroys `amount` tokens owned by `account`
+	pub fn burn(&mut self, account: u64, amount: U256) -> Result<Vec<Erc20Event<Test>>, Failure> {
+		let balance =
+			self.balance(account).checked_sub(amount).ok_or(Failure::BurnExceedsBalance)?;
+		let total_supply = match self.arithmetic {
+			Arithmetic::Checked =>
+				self.total_supply.checked_sub(amount).ok_or(Failure::Overflow)?,
+			Arithmetic::Unchecked => self.total_supply.overflowing_sub(amount).0,
+		};
+		self.total_supply = total_supply;
+		self.balances.insert(account, balance);
+
+		Ok(vec![Erc20Event::Burn { account, amount }])
+	}
+
+	/// Adds `amount` to `balance`
+	fn add(&self, balance: U256, amount: U256) -> Result<U256, Failure> {
+		match self.arithmetic {
+			Arithmetic::Checked => balance.checked_add(amount).ok_or(Failure::Overflow),
+			Arithmetic::Unchecked => Ok(balance.overflowing_add(amount).0),
+		}
+	}
+}
+
+/// Runs `ops` against both the model and the pallet, starting from the genesis state in which
+/// account `i` owns `genesis[i]` tokens, and panics on the first disagreement.
+pub fn check(genesis: &[u8], ops: &[Op]) {
+	let mut model = Model::new(genesis);
+
+	let mut ext = ExtBuilder::<Test>::default()
+		.with_balances(
+			model.balances.iter().map(|(account, balance)| (*account, *balance)).collect(),
+		)
+		.with_supply(model.total_supply)
+		.build();
+	ext.execute_with(|| {
+		for op in ops {
+			let allowances_before = stored_allowances();
+			let expected = model.apply(op);
+
+			System::reset_events();
+			let result = dispatch(op);
+
+			assert_eq!(result.is_ok(), expected.is_some(), "{:?} returned {:?}", op, result);
+			assert_eq!(erc20_events::<Test>(), expected.unwrap_or_default(), "events of {:?}", op);
+
+			assert_eq!(Erc20::total_supply().unwrap_or_default(), model.total_supply, "{:?}", op);
+			let balances = (0..ACCOUNTS as u64)
+				.map(|account| (account, Erc20::balance_of(account).unwrap_or_default()))
+				.collect::<Vec<_>>();
+			for (account, balance) in &balances {
+				assert_eq!(
+					*balance,
+					model.balance(*account),
+					"balance of {} after {:?}",
+					account,
+					op
+				);
+			}
+			assert_eq!(
+				balances.iter().fold(U256::zero(), |total, (_, balance)| total + *balance),
+				model.total_supply,
+				"supply isn't conserved by {:?}",
+				op
+			);
+
+			for ((owner, spender), allowance) in stored_allowances() {
+				assert_eq!(allowance, model.allowance(owner, spender), "{:?}", op);
+
+				let approved = match *op {
+					Op::Approve { owner: o, spender: s, .. } |
+					Op::ApproveUnlimited { owner: o, spender: s } => (account(o), account(s)) == (owner, spender),
+					_ => false,
+				};
+				let before = allowances_before.get(&(owner, spender)).copied().unwrap_or_default();
+				assert!(approved || allowance <= before, "{:?} increased an allowance", op);
+			}
+		}
+	});
+}
+
+fn account(index: u8) -> u64 {
+	(index % ACCOUNTS) as u64
+}
+
+fn dispatch(op: &Op) -> DispatchResult {
+	match *op {
+		Op::Transfer { from, to, amount } =>
+			Erc20::transfer(Origin::signed(account(from)), account(to), amount.into()),
+		Op::Approve { owner, spender, amount } =>
+			Erc20::approve(Origin::signed(account(owner)), account(spender), amount.into()),
+		Op::ApproveUnlimited { owner, spender } =>
+			Erc20::approve(Origin::signed(account(owner)), account(spender), U256::max_value()),
+		Op::TransferFrom { spender, from, to, amount } => Erc20::transfer_from(
+			Origin::signed(account(spender)),
+			account(from),
+			account(to),
+			amount.into(),
+		),
+		Op::Mint { account: to, amount } => Erc20::mint(account(to), amount.into()),
+		Op::Burn { account: from, amount } => Erc20::burn(account(from), amount.into()),
+	}
+}
+
+fn stored_allowances() -> BTreeMap<(u64, u64), U256> {
+	crate::Allowance::<Test>::iter()
+		.map(|(owner, spender, amount)| ((owner, spender), amount))
+		.collect()
+}
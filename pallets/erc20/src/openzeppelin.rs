@@ -0,0 +1,227 @@
+//! Model of OpenZeppelin's `ERC20` (contracts v4.8), which the pallet is checked against in
+//! `differential.rs`.
+//!
+//! It's the reference model of `model.rs` with what sets OpenZeppelin apart: `ZERO_ADDRESS`
+//! standing for `address(0)`, the allowance being spent before the transfer, mints and burns being
+//! transfers from and to `address(0)`, and the unchecked arithmetic. Accounts are the `u64` account
+//! ids of the mock runtime. Like in the EVM, a reverted call leaves no trace: neither state changes
+//! nor events.
+
+use crate::{
+	mock::Test,
+	model::{Arithmetic, Failure, Model},
+	Event as Erc20Event,
+};
+use sp_core::U256;
+use std::fmt;
+
+/// `address(0)`, which OpenZeppelin rejects as a sender, recipient, owner or spender
+pub const ZERO_ADDRESS: u64 = 0;
+
+/// Events of `IERC20`, mints and burns being transfers from and to `address(0)`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OzEvent {
+	Transfer { from: u64, to: u64, value: U256 },
+	Approval { owner: u64, spender: u64, value: U256 },
+}
+
+impl OzEvent {
+	/// `IERC20` event corresponding to an event of the pallet, if any
+	pub fn from_event(event: Erc20Event<Test>) -> Option<Self> {
+		Some(match event {
+			Erc20Event::Transfer { from, to, amount } => Self::Transfer { from, to, value: amount },
+			Erc20Event::Approval { owner, spender, amount } =>
+				Self::Approval { owner, spender, value: amount },
+			Erc20Event::Mint { account, amount } =>
+				Self::Transfer { from: ZERO_ADDRESS, to: account, value: amount },
+			Erc20Event::Burn { account, amount } =>
+				Self::Transfer { from: account, to: ZERO_ADDRESS, value: amount },
+			_ => return None,
+		})
+	}
+}
+
+/// Reasons for which the contract reverts, debug-printed as the revert reason of the contract
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Revert {
+	TransferFromZero,
+	TransferToZero,
+	TransferExceedsBalance,
+	ApproveFromZero,
+	ApproveToZero,
+	InsufficientAllowance,
+	MintToZero,
+	BurnFromZero,
+	BurnExceedsBalance,
+	/// `Panic(0x11)`, raised by the checked arithmetic of Solidity 0.8
+	ArithmeticOverflow,
+}
+
+impl Revert {
+	/// Revert reason of the contract
+	pub fn reason(&self) -> &'static str {
+		match self {
+			Self::TransferFromZero => "ERC20: transfer from the zero address",
+			Self::TransferToZero => "ERC20: transfer to the zero address",
+			Self::TransferExceedsBalance => "ERC20: transfer amount exceeds balance",
+			Self::ApproveFromZero => "ERC20: approve from the zero address",
+			Self::ApproveToZero => "ERC20: approve to the zero address",
+			Self::InsufficientAllowance => "ERC20: insufficient allowance",
+			Self::MintToZero => "ERC20: mint to the zero address",
+			Self::BurnFromZero => "ERC20: burn from the zero address",
+			Self::BurnExceedsBalance => "ERC20: burn amount exceeds balance",
+			Self::ArithmeticOverflow => "Panic(0x11)",
+		}
+	}
+}
+
+impl fmt::Debug for Revert {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.reason())
+	}
+}
+
+impl From<Failure> for Revert {
+	fn from(failure: Failure) -> Self {
+		match failure {
+			Failure::InsufficientFunds => Self::TransferExceedsBalance,
+			Failure::InsufficientAllowance => Self::InsufficientAllowance,
+			Failure::BurnExceedsBalance => Self::BurnExceedsBalance,
+			// only the total supply is added to with checked arithmetic
+			Failure::Overflow => Self::ArithmeticOverflow,
+		}
+	}
+}
+
+/// State of the contract
+#[derive(Clone, Debug)]
+pub struct OzErc20 {
+	model: Model,
+}
+
+impl OzErc20 {
+	/// Contract deployed with the given state, which (unlike with a real deployment) doesn't have
+	/// to be consistent
+	pub fn new(total_supply: U256, balances: impl IntoIterator<Item = (u64, U256)>) -> Self {
+		Self { model: Model::with_state(total_supply, balances, Arithmetic::Unchecked) }
+	}
+
+	pub fn total_supply(&self) -> U256 {
+		self.model.total_supply()
+	}
+
+	pub fn balance_of(&self, account: u64) -> U256 {
+		self.model.balance(account)
+	}
+
+	pub fn allowance(&self, owner: u64, spender: u64) -> U256 {
+		self.model.allowance(owner, spender)
+	}
+
+	/// `transfer(to, amount)` sent by `sender`
+	pub fn transfer(&mut self, sender: u64, to: u64, amount: U256) -> Result<Vec<OzEvent>, Revert> {
+		self.call(|model| transfer_impl(model, sender, to, amount))
+	}
+
+	/// `approve(spender, amount)` sent by `sender`
+	pub fn approve(
+		&mut self,
+		sender: u64,
+		spender: u64,
+		amount: U256,
+	) -> Result<Vec<OzEvent>, Revert> {
+		self.call(|model| approve_impl(model, sender, spender, amount))
+	}
+
+	/// `transferFrom(from, to, amount)` sent by `sender`, which spends the allowance (emitting its
+	/// `Approval`) before transferring
+	pub fn transfer_from(
+		&mut self,
+		sender: u64,
+		from: u64,
+		to: u64,
+		amount: U256,
+	) -> Result<Vec<OzEvent>, Revert> {
+		self.call(|model| {
+			// `_spendAllowance(from, sender, amount)`, an infinite allowance is never spent
+			let mut events = match model.spent_allowance(from, sender, amount)? {
+				Some(allowance) => approve_impl(model, from, sender, allowance)?,
+				None => vec![],
+			};
+			events.extend(transfer_impl(model, from, to, amount)?);
+			Ok(events)
+		})
+	}
+
+	/// Internal `_mint(account, amount)`
+	pub fn mint(&mut self, account: u64, amount: U256) -> Result<Vec<OzEvent>, Revert> {
+		self.call(|model| {
+			if account == ZERO_ADDRESS {
+				return Err(Revert::MintToZero)
+			}
+
+			Ok(oz_events(model.mint(account, amount)?))
+		})
+	}
+
+	/// Internal `_burn(account, amount)`
+	pub fn burn(&mut self, account: u64, amount: U256) -> Result<Vec<OzEvent>, Revert> {
+		self.call(|model| {
+			if account == ZERO_ADDRESS {
+				return Err(Revert::BurnFromZero)
+			}
+
+			Ok(oz_events(model.burn(account, amount)?))
+		})
+	}
+
+	/// Runs `f` as a call to the contract, whose changes are only kept if it doesn't revert
+	fn call(
+		&mut self,
+		f: impl FnOnce(&mut Model) -> Result<Vec<OzEvent>, Revert>,
+	) -> Result<Vec<OzEvent>, Revert> {
+		let mut model = self.model.clone();
+		let events = f(&mut model)?;
+
+		self.model = model;
+		Ok(events)
+	}
+}
+
+/// `_transfer(from, to, amount)`
+fn transfer_impl(
+	model: &mut Model,
+	from: u64,
+	to: u64,
+	amount: U256,
+) -> Result<Vec<OzEvent>, Revert> {
+	if from == ZERO_ADDRESS {
+		return Err(Revert::TransferFromZero)
+	}
+	if to == ZERO_ADDRESS {
+		return Err(Revert::TransferToZero)
+	}
+
+	Ok(oz_events(model.transfer(from, to, amount)?))
+}
+
+/// `_approve(owner, spender, amount)`
+fn approve_impl(
+	model: &mut Model,
+	owner: u64,
+	spender: u64,
+	amount: U256,
+) -> Result<Vec<OzEvent>, Revert> {
+	if owner == ZERO_ADDRESS {
+		return Err(Revert::ApproveFromZero)
+	}
+	if spender == ZERO_ADDRESS {
+		return Err(Revert::ApproveToZero)
+	}
+
+	Ok(oz_events(model.approve(owner, spender, amount)))
+}
+
+fn oz_events(events: Vec<Erc20Event<Test>>) -> Vec<OzEvent> {
+	events.into_iter().filter_map(OzEvent::from_event).collect()
+}
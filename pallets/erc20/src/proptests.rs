@@ -0,0 +1,43 @@
+use crate::model::{self, Op};
+use proptest::prelude::*;
+
+fn op() -> impl Strategy<Value = Op> {
+	let account = 0..model::ACCOUNTS;
+	prop_oneof![
+		(account.clone(), account.clone(), any::<u8>())
+			.prop_map(|(from, to, amount)| Op::Transfer { from, to, amount }),
+		(account.clone(), account.clone(), any::<u8>())
+			.prop_map(|(owner, spender, amount)| Op::Approve { owner, spender, amount }),
+		(account.clone(), account.clone())
+			.prop_map(|(owner, spender)| Op::ApproveUnlimited { owner, spender }),
+		(account.clone(), account.clone(), account.clone(), any::<u8>())
+			.prop_map(|(spender, from, to, amount)| Op::TransferFrom { spender, from, to, amount }),
+		(account.clone(), any::<u8>()).prop_map(|(account, amount)| Op::Mint { account, amount }),
+		(account, any::<u8>()).prop_map(|(account, amount)| Op::Burn { account, amount }),
+	]
+}
+
+proptest! {
+	#[test]
+	fn test_pallet_matches_model(
+		genesis in prop::collection::vec(any::<u8>(), model::ACCOUNTS as usize),
+		ops in prop::collection::vec(op(), 0..64),
+	) {
+		model::check(&genesis, &ops);
+	}
+}
+
+#[test]
+fn test_model_regressions() {
+	// an infinite allowance is never spent, a self transfer moves nothing
+	model::check(
+		&[10, 0, 0, 0],
+		&[
+			Op::ApproveUnlimited { owner: 0, spender: 1 },
+			Op::TransferFrom { spender: 1, from: 0, to: 0, amount: 10 },
+			Op::TransferFrom { spender: 1, from: 0, to: 2, amount: 10 },
+			Op::TransferFrom { spender: 1, from: 0, to: 2, amount: 1 },
+			Op::Burn { account: 2, amount: 11 },
+		],
+	);
+}
@@ -0,0 +1,152 @@
+//! Test support, for this pallet and for downstream pallets (behind the `test-utils` feature).
+//!
+//! Everything is generic over the runtime, so it works with any mock runtime that includes the
+//! pallet:
+//!
+//! ```ignore
+//! ExtBuilder::<Test>::default()
+//! 	.with_balances(vec![(1, 100.into()), (2, 50.into())])
+//! 	.with_allowances(vec![(1, 2, 10.into())])
+//! 	.build()
+//! 	.execute_with(|| {
+//! 		assert_ok!(Erc20::transfer_from(Origin::signed(2), 1, 3, 10.into()));
+//! 		assert_erc20_events!(
+//! 			Test,
+//! 			Event::Transfer { from: 1, to: 3, amount: 10.into() },
+//! 			Event::Approval { owner: 1, spender: 2, amount: 0.into() },
+//! 		);
+//! 		assert_supply_consistent::<Test>();
+//! 	});
+//! ```
+
+use crate::{
+	Allowance, Balance, ClaimOwnerOf, Config, Event, GenesisConfig, Pallet, TotalSupply,
+	WrappedSupply,
+};
+use frame_support::traits::{Currency, GenesisBuild};
+use sp_core::U256;
+use sp_runtime::traits::One;
+
+/// Builder of externalities with a given state of the pallet, at block 1.
+pub struct ExtBuilder<T: Config> {
+	balances: Vec<(T::AccountId, U256)>,
+	allowances: Vec<(T::AccountId, T::AccountId, U256)>,
+	claims: Vec<(ClaimOwnerOf<T>, U256)>,
+	supply: Option<U256>,
+}
+
+impl<T: Config> Default for ExtBuilder<T> {
+	fn default() -> Self {
+		Self { balances: vec![], allowances: vec![], claims: vec![], supply: None }
+	}
+}
+
+impl<T: Config> ExtBuilder<T> {
+	/// Initial balances of the accounts
+	pub fn with_balances(mut self, balances: Vec<(T::AccountId, U256)>) -> Self {
+		self.balances = balances;
+		self
+	}
+
+	/// Initial allowances, as `(owner, spender, amount)`
+	pub fn with_allowances(mut self, allowances: Vec<(T::AccountId, T::AccountId, U256)>) -> Self {
+		self.allowances = allowances;
+		self
+	}
+
+	/// Airdrop allocations, which don't count in the total supply
+	pub fn with_claims(mut self, claims: Vec<(ClaimOwnerOf<T>, U256)>) -> Self {
+		self.claims = claims;
+		self
+	}
+
+	/// Initial total supply, the sum of the balances by default. Any other value makes the state
+	/// inconsistent on purpose
+	pub fn with_supply(mut self, supply: U256) -> Self {
+		self.supply = Some(supply);
+		self
+	}
+
+	/// Builds the externalities.
+	///
+	/// Panics if the balances sum up to more than `U256::max_value()` and no supply is given.
+	pub fn build(self) -> sp_io::TestExternalities {
+		let mut storage = frame_system::GenesisConfig::default().build_storage::<T>().unwrap();
+
+		let total_supply = self.supply.unwrap_or_else(|| {
+			self.balances.iter().fold(U256::zero(), |total, (_, balance)| {
+				total.checked_add(*balance).expect("the balances overflow, give the supply")
+			})
+		});
+		GenesisConfig::<T> { total_supply, balances: self.balances, claims: self.claims }
+			.assimilate_storage(&mut storage)
+			.unwrap();
+
+		let mut ext = sp_io::TestExternalities::new(storage);
+		ext.execute_with(|| {
+			for (owner, spender, amount) in self.allowances {
+				<Allowance<T>>::insert(owner, spender, amount);
+			}
+			// events are only deposited from block 1 on
+			frame_system::Pallet::<T>::set_block_number(One::one());
+		});
+		ext
+	}
+}
+
+/// Events of the pallet deposited so far, in order
+pub fn erc20_events<T: Config>() -> Vec<Event<T>>
+where
+	<T as frame_system::Config>::Event: TryInto<Event<T>>,
+{
+	frame_system::Pallet::<T>::events()
+		.into_iter()
+		.filter_map(|record| record.event.try_into().ok())
+		.collect()
+}
+
+/// Asserts that the events of the pallet deposited so far are exactly `expected`, see
+/// `assert_erc20_events!`
+#[track_caller]
+pub fn assert_erc20_events_eq<T: Config>(expected: Vec<Event<T>>)
+where
+	<T as frame_system::Config>::Event: TryInto<Event<T>>,
+{
+	assert_eq!(erc20_events::<T>(), expected);
+}
+
+/// Asserts that the total supply is the sum of all the balances (including the tokens held by the
+/// pallet account, e.g. in escrows).
+#[track_caller]
+pub fn assert_supply_consistent<T: Config>() {
+	let sum = <Balance<T>>::iter_values().fold(U256::zero(), |total, balance| {
+		total.checked_add(balance).expect("the balances overflow")
+	});
+
+	assert_eq!(<TotalSupply<T>>::get().unwrap_or_default(), sum, "inconsistent total supply");
+}
+
+/// Asserts that the wrapped supply is the equivalent of the native currency held by the pallet
+/// account on top of its existential deposit, which must not have received native tokens other
+/// than by `wrap`.
+#[track_caller]
+pub fn assert_wrapped_supply_consistent<T: Config>() {
+	let locked = T::NativeCurrency::free_balance(&Pallet::<T>::account_id()) -
+		T::NativeCurrency::minimum_balance();
+
+	assert_eq!(
+		Pallet::<T>::wrapped_amount(locked),
+		Some(<WrappedSupply<T>>::get()),
+		"inconsistent wrapped supply"
+	);
+}
+
+/// Asserts that the events of the pallet deposited so far in the runtime (other pallets' events
+/// are ignored) are exactly the given ones, in order, e.g.
+/// `assert_erc20_events!(Test, Event::Mint { account: 1, amount: 10.into() })`.
+#[macro_export]
+macro_rules! assert_erc20_events {
+	($runtime:ty $(, $event:expr)* $(,)?) => {
+		$crate::test_utils::assert_erc20_events_eq::<$runtime>(vec![$($event),*])
+	};
+}
@@ -1,6 +1,29 @@
-use crate::{mock, mock::*, Error};
-use frame_support::{assert_noop, assert_ok};
-use sp_core::U256;
+use crate::{
+	abi::{self, AbiError, Erc20Function},
+	assert_erc20_events, convert_decimals,
+	mock::*,
+	test_utils::*,
+	AirdropClaimedBitmap, AirdropsBeingSwept, ClaimOwner, ClaimSignature, EcdsaSignature, Error,
+	Escrow, EthereumAddress, EthereumCall, EthereumPayload, FailurePolicy, HashLock, MerkleRoot,
+	TransferSimulation, FAILING_ETHEREUM_CALL, NO_CLAIM, NO_OP_ETHEREUM_CALL,
+	RATE_LIMITED_ETHEREUM_ADDRESS, UNLINKED_ETHEREUM_ADDRESS,
+};
+use codec::Encode;
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::DispatchResult,
+	traits::{Currency, Get, Hooks, UnfilteredDispatchable},
+	unsigned::{TransactionSource, ValidateUnsigned},
+	weights::GetDispatchInfo,
+};
+use sp_core::{hexdisplay::HexDisplay, H256, U256};
+use sp_io::hashing::keccak_256;
+use sp_runtime::{
+	testing::TestSignature,
+	traits::BadOrigin,
+	transaction_validity::{InvalidTransaction, TransactionValidity, ValidTransaction},
+	DispatchError,
+};
 
 #[test]
 fn test_transfer() {
@@ -16,15 +39,10 @@ fn test_transfer() {
 		assert_eq!(Erc20::balance_of(1), Some(90.into()));
 		assert_eq!(Erc20::balance_of(2), Some(100.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 0, amount: 10.into() }),
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 2, amount: 10.into() }),
-			]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: 0, amount: 10.into() },
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
 		);
 	});
 }
@@ -40,13 +58,7 @@ fn test_self_transfer() {
 
 		assert_eq!(Erc20::balance_of(1), Some(110.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Transfer { from: 1, to: 1, amount: 10.into() }),]
-		);
+		assert_erc20_events!(Test, crate::Event::Transfer { from: 1, to: 1, amount: 10.into() });
 	});
 }
 
@@ -60,13 +72,7 @@ fn test_transfer_no_funds() {
 			Error::<Test>::InsufficientFunds
 		);
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![]
-		);
+		assert_erc20_events!(Test);
 	});
 }
 
@@ -98,30 +104,13 @@ fn test_allowance() {
 		assert_eq!(Erc20::balance_of(1), Some(90.into()));
 		assert_eq!(Erc20::balance_of(2), Some(100.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 0,
-					amount: 20.into()
-				}),
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 0, amount: 10.into() }),
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 0,
-					amount: 10.into()
-				}),
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 2, amount: 10.into() }),
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 0,
-					amount: 0.into()
-				}),
-			]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 0, amount: 20.into() },
+			crate::Event::Transfer { from: 1, to: 0, amount: 10.into() },
+			crate::Event::Approval { owner: 1, spender: 0, amount: 10.into() },
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
+			crate::Event::Approval { owner: 1, spender: 0, amount: 0.into() },
 		);
 	});
 }
@@ -138,24 +127,11 @@ fn test_allowance_self_transfer() {
 
 		assert_eq!(Erc20::balance_of(1), Some(110.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 1,
-					amount: 100.into()
-				}),
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 1, amount: 10.into() }),
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 1,
-					amount: 90.into()
-				}),
-			]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 1, amount: 100.into() },
+			crate::Event::Transfer { from: 1, to: 1, amount: 10.into() },
+			crate::Event::Approval { owner: 1, spender: 1, amount: 90.into() },
 		);
 	});
 }
@@ -173,16 +149,9 @@ fn test_transfer_no_allowance() {
 			Error::<Test>::InsufficientAllowance
 		);
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Approval {
-				owner: 1,
-				spender: 0,
-				amount: 20.into()
-			})]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 0, amount: 20.into() }
 		);
 	});
 }
@@ -202,16 +171,9 @@ fn test_allowance_no_funds() {
 			Error::<Test>::InsufficientFunds
 		);
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Approval {
-				owner: 1,
-				spender: 0,
-				amount: 120.into()
-			})]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 0, amount: 120.into() }
 		);
 	});
 }
@@ -228,16 +190,9 @@ fn test_allowance_overflow() {
 			Error::<Test>::Overflow
 		);
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Approval {
-				owner: 3,
-				spender: 0,
-				amount: U256::max_value()
-			})]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 3, spender: 0, amount: U256::max_value() }
 		);
 	});
 }
@@ -253,25 +208,60 @@ fn test_unlimited_allowance() {
 
 		assert_eq!(Erc20::allowance(1, 0), Some(U256::max_value()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![
-				mock::Event::from(crate::Event::Approval {
-					owner: 1,
-					spender: 0,
-					amount: U256::max_value()
-				}),
-				// notice: no approval updates here (it's unlimited)
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 0, amount: 1.into() }),
-				mock::Event::from(crate::Event::Transfer { from: 1, to: 0, amount: 2.into() })
-			]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 0, amount: U256::max_value() },
+			// notice: no approval updates here (it's unlimited)
+			crate::Event::Transfer { from: 1, to: 0, amount: 1.into() },
+			crate::Event::Transfer { from: 1, to: 0, amount: 2.into() }
 		);
 	});
 }
 
+#[test]
+fn test_genesis_allowance() {
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 100.into()), (2, 50.into())])
+		.with_allowances(vec![(1, 2, 30.into())])
+		.build()
+		.execute_with(|| {
+			assert_eq!(Erc20::total_supply(), Some(150.into()));
+			assert_eq!(Erc20::allowance(1, 2), Some(30.into()));
+
+			assert_ok!(Erc20::transfer_from(Origin::signed(2), 1, 3, 30.into()));
+			assert_noop!(
+				Erc20::transfer_from(Origin::signed(2), 1, 3, 1.into()),
+				Error::<Test>::InsufficientAllowance
+			);
+
+			assert_eq!(Erc20::balance_of(1), Some(70.into()));
+			assert_eq!(Erc20::balance_of(3), Some(30.into()));
+			assert_erc20_events!(
+				Test,
+				crate::Event::Transfer { from: 1, to: 3, amount: 30.into() },
+				crate::Event::Approval { owner: 1, spender: 2, amount: 0.into() },
+			);
+			assert_supply_consistent::<Test>();
+		});
+}
+
+#[test]
+fn test_supply_consistency() {
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 100.into()), (2, 50.into())])
+		.build()
+		.execute_with(|| {
+			assert_ok!(Erc20::transfer(Origin::signed(1), 2, 10.into()));
+			assert_ok!(Erc20::mint(3, 25.into()));
+			assert_ok!(Erc20::burn(2, 60.into()));
+			// the escrowed tokens are held by the pallet account, and still count
+			assert_ok!(Erc20::create_escrow(Origin::signed(1), 2, 40.into(), 5));
+
+			assert_eq!(Erc20::total_supply(), Some(115.into()));
+			assert_supply_consistent::<Test>();
+		});
+}
+
 #[test]
 fn test_mint() {
 	new_test_ext().execute_with(|| {
@@ -283,13 +273,7 @@ fn test_mint() {
 		assert_eq!(Erc20::balance_of(1), Some(210.into()));
 		assert_eq!(Erc20::total_supply(), Some(300.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Mint { account: 1, amount: 100.into() })]
-		);
+		assert_erc20_events!(Test, crate::Event::Mint { account: 1, amount: 100.into() });
 	});
 }
 
@@ -306,15 +290,9 @@ fn test_mint_overflow() {
 		assert_eq!(Erc20::balance_of(1), Some(U256::max_value() - U256::from(90)));
 		assert_eq!(Erc20::total_supply(), Some(U256::max_value()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Mint {
-				account: 1,
-				amount: U256::max_value() - U256::from(200)
-			})]
+		assert_erc20_events!(
+			Test,
+			crate::Event::Mint { account: 1, amount: U256::max_value() - U256::from(200) }
 		);
 	});
 }
@@ -330,13 +308,7 @@ fn test_burn() {
 		assert_eq!(Erc20::balance_of(1), Some(10.into()));
 		assert_eq!(Erc20::total_supply(), Some(100.into()));
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![mock::Event::from(crate::Event::Burn { account: 1, amount: 100.into() })]
-		);
+		assert_erc20_events!(Test, crate::Event::Burn { account: 1, amount: 100.into() });
 	});
 }
 
@@ -348,13 +320,7 @@ fn test_burn_no_balance() {
 
 		assert_noop!(Erc20::burn(1, 200.into()), Error::<Test>::BurnExceedsBalance);
 
-		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![]
-		);
+		assert_erc20_events!(Test);
 	});
 }
 
@@ -366,12 +332,1844 @@ fn test_burn_overflow() {
 
 		assert_noop!(Erc20::burn(3, 300.into()), Error::<Test>::Overflow);
 
+		assert_erc20_events!(Test);
+	});
+}
+
+#[test]
+fn test_escrow_release() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 2, 10.into(), 5));
+
+		assert_eq!(Erc20::balance_of(1), Some(100.into()));
+		assert_eq!(Erc20::balance_of(pallet), Some(10.into()));
 		assert_eq!(
-			<frame_system::Pallet<Test>>::events()
-				.into_iter()
-				.map(|ev| ev.event)
-				.collect::<Vec<_>>(),
-			vec![]
+			Erc20::escrow(0),
+			Some(Escrow { from: 1, to: 2, amount: 10.into(), expires_at: 5 })
+		);
+
+		assert_ok!(Erc20::release(Origin::signed(1), 0));
+
+		assert_eq!(Erc20::balance_of(2), Some(100.into()));
+		assert_eq!(Erc20::balance_of(pallet), Some(0.into()));
+		assert_eq!(Erc20::escrow(0), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 10.into() },
+			crate::Event::EscrowCreated { id: 0, from: 1, to: 2, amount: 10.into(), expires_at: 5 },
+			crate::Event::Transfer { from: pallet, to: 2, amount: 10.into() },
+			crate::Event::EscrowReleased { id: 0 },
+		);
+	});
+}
+
+#[test]
+fn test_escrow_refund() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 2, 10.into(), 5));
+		assert_ok!(Erc20::refund(Origin::signed(2), 0));
+
+		assert_eq!(Erc20::balance_of(1), Some(110.into()));
+		assert_eq!(Erc20::balance_of(2), Some(90.into()));
+		assert_eq!(Erc20::escrow(0), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 10.into() },
+			crate::Event::EscrowCreated { id: 0, from: 1, to: 2, amount: 10.into(), expires_at: 5 },
+			crate::Event::Transfer { from: pallet, to: 1, amount: 10.into() },
+			crate::Event::EscrowRefunded { id: 0 },
+		);
+	});
+}
+
+#[test]
+fn test_escrow_expiry() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+
+		// only two escrows can be expired automatically in a single block in the mock runtime
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 0, 10.into(), 5));
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 0, 20.into(), 5));
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 0, 30.into(), 5));
+		assert_ok!(Erc20::release(Origin::signed(1), 0));
+
+		assert_eq!(Erc20::balance_of(1), Some(50.into()));
+
+		System::set_block_number(5);
+		Erc20::on_initialize(5);
+
+		assert_eq!(Erc20::balance_of(1), Some(70.into()));
+		assert_eq!(Erc20::escrow(1), None);
+		// the third escrow did not fit into the expiry queue
+		assert!(Erc20::escrow(2).is_some());
+
+		assert_ok!(Erc20::claim_expired(Origin::signed(2), 2));
+
+		assert_eq!(Erc20::balance_of(0), Some(10.into()));
+		assert_eq!(Erc20::balance_of(1), Some(100.into()));
+		assert_eq!(Erc20::escrow(2), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 10.into() },
+			crate::Event::EscrowCreated { id: 0, from: 1, to: 0, amount: 10.into(), expires_at: 5 },
+			crate::Event::Transfer { from: 1, to: pallet, amount: 20.into() },
+			crate::Event::EscrowCreated { id: 1, from: 1, to: 0, amount: 20.into(), expires_at: 5 },
+			crate::Event::Transfer { from: 1, to: pallet, amount: 30.into() },
+			crate::Event::EscrowCreated { id: 2, from: 1, to: 0, amount: 30.into(), expires_at: 5 },
+			crate::Event::Transfer { from: pallet, to: 0, amount: 10.into() },
+			crate::Event::EscrowReleased { id: 0 },
+			// the released escrow is skipped by the automatic expiry
+			crate::Event::Transfer { from: pallet, to: 1, amount: 20.into() },
+			crate::Event::EscrowExpired { id: 1 },
+			crate::Event::Transfer { from: pallet, to: 1, amount: 30.into() },
+			crate::Event::EscrowExpired { id: 2 },
+		);
+	});
+}
+
+#[test]
+fn test_escrow_errors() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Erc20::create_escrow(Origin::signed(1), 2, 10.into(), 1),
+			Error::<Test>::EscrowExpiryInPast
+		);
+		assert_noop!(
+			Erc20::create_escrow(Origin::signed(1), 2, 120.into(), 5),
+			Error::<Test>::InsufficientFunds
+		);
+
+		assert_ok!(Erc20::create_escrow(Origin::signed(1), 2, 10.into(), 5));
+
+		assert_noop!(Erc20::release(Origin::signed(2), 0), Error::<Test>::NotEscrowSender);
+		assert_noop!(Erc20::refund(Origin::signed(1), 0), Error::<Test>::NotEscrowRecipient);
+		assert_noop!(Erc20::claim_expired(Origin::signed(1), 0), Error::<Test>::EscrowNotExpired);
+		assert_noop!(Erc20::release(Origin::signed(1), 1), Error::<Test>::UnknownEscrow);
+	});
+}
+
+#[test]
+fn test_htlc_claim() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+		let blake2_preimage = [1; 32];
+		let sha2_preimage = [2; 32];
+		let blake2_lock = HashLock::Blake2_256(sp_io::hashing::blake2_256(&blake2_preimage).into());
+		let sha2_lock = HashLock::Sha2_256(sp_io::hashing::sha2_256(&sha2_preimage).into());
+
+		assert_ok!(Erc20::lock_htlc(Origin::signed(1), blake2_lock, 0, 10.into(), 5));
+		assert_ok!(Erc20::lock_htlc(Origin::signed(1), sha2_lock, 2, 20.into(), 5));
+
+		assert_eq!(Erc20::balance_of(1), Some(80.into()));
+		assert_eq!(Erc20::balance_of(pallet), Some(30.into()));
+
+		// the preimage can be revealed by anyone
+		assert_ok!(Erc20::claim_htlc(Origin::signed(3), blake2_preimage));
+		assert_ok!(Erc20::claim_htlc(Origin::signed(2), sha2_preimage));
+
+		assert_eq!(Erc20::balance_of(0), Some(10.into()));
+		assert_eq!(Erc20::balance_of(2), Some(110.into()));
+		assert_eq!(Erc20::balance_of(pallet), Some(0.into()));
+		assert_eq!(Erc20::htlc(blake2_lock), None);
+		assert_eq!(Erc20::htlc(sha2_lock), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 10.into() },
+			crate::Event::HtlcLocked {
+				hashlock: blake2_lock,
+				sender: 1,
+				recipient: 0,
+				amount: 10.into(),
+				timelock: 5
+			},
+			crate::Event::Transfer { from: 1, to: pallet, amount: 20.into() },
+			crate::Event::HtlcLocked {
+				hashlock: sha2_lock,
+				sender: 1,
+				recipient: 2,
+				amount: 20.into(),
+				timelock: 5
+			},
+			crate::Event::Transfer { from: pallet, to: 0, amount: 10.into() },
+			crate::Event::HtlcClaimed { hashlock: blake2_lock, preimage: blake2_preimage },
+			crate::Event::Transfer { from: pallet, to: 2, amount: 20.into() },
+			crate::Event::HtlcClaimed { hashlock: sha2_lock, preimage: sha2_preimage },
+		);
+	});
+}
+
+#[test]
+fn test_htlc_refund() {
+	new_test_ext().execute_with(|| {
+		let preimage = [1; 32];
+		let hashlock = HashLock::Sha2_256(sp_io::hashing::sha2_256(&preimage).into());
+
+		assert_ok!(Erc20::lock_htlc(Origin::signed(1), hashlock, 2, 10.into(), 5));
+		assert_noop!(
+			Erc20::refund_htlc(Origin::signed(1), hashlock),
+			Error::<Test>::HtlcNotExpired
+		);
+
+		System::set_block_number(5);
+
+		assert_noop!(Erc20::claim_htlc(Origin::signed(2), preimage), Error::<Test>::HtlcExpired);
+		assert_ok!(Erc20::refund_htlc(Origin::signed(1), hashlock));
+
+		assert_eq!(Erc20::balance_of(1), Some(110.into()));
+		assert_eq!(Erc20::balance_of(2), Some(90.into()));
+		assert_eq!(Erc20::htlc(hashlock), None);
+	});
+}
+
+#[test]
+fn test_htlc_errors() {
+	new_test_ext().execute_with(|| {
+		let preimage = [1; 32];
+		let hashlock = HashLock::Blake2_256(sp_io::hashing::blake2_256(&preimage).into());
+
+		assert_noop!(
+			Erc20::lock_htlc(Origin::signed(1), hashlock, 2, 10.into(), 1),
+			Error::<Test>::HtlcTimelockInPast
+		);
+		assert_noop!(
+			Erc20::lock_htlc(Origin::signed(1), hashlock, 2, 120.into(), 5),
+			Error::<Test>::InsufficientFunds
+		);
+
+		assert_ok!(Erc20::lock_htlc(Origin::signed(1), hashlock, 2, 10.into(), 5));
+
+		assert_noop!(
+			Erc20::lock_htlc(Origin::signed(2), hashlock, 1, 10.into(), 5),
+			Error::<Test>::HtlcAlreadyExists
+		);
+		// the right secret, but hashed with another algorithm
+		assert_noop!(
+			Erc20::refund_htlc(
+				Origin::signed(1),
+				HashLock::Sha2_256(sp_io::hashing::sha2_256(&preimage).into())
+			),
+			Error::<Test>::UnknownHtlc
+		);
+		assert_noop!(Erc20::claim_htlc(Origin::signed(2), [2; 32]), Error::<Test>::UnknownHtlc);
+	});
+}
+
+#[test]
+fn test_stream_withdraw() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+
+		// 5 tokens per block from block 2 to block 6
+		assert_ok!(Erc20::create_stream(Origin::signed(1), 2, 5.into(), 2, 6, 20.into()));
+
+		assert_eq!(Erc20::balance_of(1), Some(90.into()));
+		assert_eq!(Erc20::stream_withdrawable(0), Some(0.into()));
+
+		System::set_block_number(4);
+		assert_eq!(Erc20::stream_withdrawable(0), Some(10.into()));
+		assert_ok!(Erc20::withdraw_from_stream(Origin::signed(2), 0));
+		assert_eq!(Erc20::balance_of(2), Some(100.into()));
+		assert_eq!(Erc20::stream_withdrawable(0), Some(0.into()));
+
+		// nothing is streamed after the stop block
+		System::set_block_number(10);
+		assert_eq!(Erc20::stream_withdrawable(0), Some(10.into()));
+		assert_ok!(Erc20::withdraw_from_stream(Origin::signed(2), 0));
+		assert_eq!(Erc20::balance_of(2), Some(110.into()));
+		assert_eq!(Erc20::balance_of(pallet), Some(0.into()));
+
+		// fully withdrawn stream is removed
+		assert_eq!(Erc20::stream(0), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 20.into() },
+			crate::Event::StreamCreated {
+				id: 0,
+				sender: 1,
+				recipient: 2,
+				rate_per_block: 5.into(),
+				start: 2,
+				stop: 6,
+				deposit: 20.into()
+			},
+			crate::Event::Transfer { from: pallet, to: 2, amount: 10.into() },
+			crate::Event::StreamWithdrawn { id: 0, amount: 10.into() },
+			crate::Event::Transfer { from: pallet, to: 2, amount: 10.into() },
+			crate::Event::StreamWithdrawn { id: 0, amount: 10.into() },
+		);
+	});
+}
+
+#[test]
+fn test_stream_cancel() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+
+		assert_ok!(Erc20::create_stream(Origin::signed(1), 2, 5.into(), 2, 6, 20.into()));
+
+		System::set_block_number(3);
+		assert_ok!(Erc20::withdraw_from_stream(Origin::signed(2), 0));
+
+		System::set_block_number(5);
+		assert_ok!(Erc20::cancel_stream(Origin::signed(1), 0));
+
+		// 15 tokens were streamed to the recipient, 5 are returned to the sender
+		assert_eq!(Erc20::balance_of(1), Some(95.into()));
+		assert_eq!(Erc20::balance_of(2), Some(105.into()));
+		assert_eq!(Erc20::stream(0), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: pallet, amount: 20.into() },
+			crate::Event::StreamCreated {
+				id: 0,
+				sender: 1,
+				recipient: 2,
+				rate_per_block: 5.into(),
+				start: 2,
+				stop: 6,
+				deposit: 20.into()
+			},
+			crate::Event::Transfer { from: pallet, to: 2, amount: 5.into() },
+			crate::Event::StreamWithdrawn { id: 0, amount: 5.into() },
+			crate::Event::Transfer { from: pallet, to: 2, amount: 10.into() },
+			crate::Event::Transfer { from: pallet, to: 1, amount: 5.into() },
+			crate::Event::StreamCancelled {
+				id: 0,
+				recipient_amount: 10.into(),
+				sender_amount: 5.into()
+			},
+		);
+	});
+}
+
+#[test]
+fn test_stream_errors() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Erc20::create_stream(Origin::signed(1), 2, 5.into(), 0, 4, 20.into()),
+			Error::<Test>::InvalidStreamSchedule
+		);
+		assert_noop!(
+			Erc20::create_stream(Origin::signed(1), 2, 5.into(), 4, 4, 0.into()),
+			Error::<Test>::InvalidStreamSchedule
+		);
+		assert_noop!(
+			Erc20::create_stream(Origin::signed(1), 2, 5.into(), 2, 6, 25.into()),
+			Error::<Test>::StreamDepositMismatch
+		);
+		assert_noop!(
+			Erc20::create_stream(Origin::signed(1), 2, U256::max_value(), 2, 6, 20.into()),
+			Error::<Test>::Overflow
+		);
+		assert_noop!(
+			Erc20::create_stream(Origin::signed(1), 2, 50.into(), 2, 6, 200.into()),
+			Error::<Test>::InsufficientFunds
+		);
+
+		assert_ok!(Erc20::create_stream(Origin::signed(1), 2, 5.into(), 2, 6, 20.into()));
+
+		assert_noop!(
+			Erc20::withdraw_from_stream(Origin::signed(1), 0),
+			Error::<Test>::NotStreamRecipient
+		);
+		assert_noop!(Erc20::cancel_stream(Origin::signed(0), 0), Error::<Test>::NotStreamParty);
+		assert_noop!(Erc20::cancel_stream(Origin::signed(1), 1), Error::<Test>::UnknownStream);
+	});
+}
+
+fn run_to_block(n: u64) {
+	while System::block_number() < n {
+		System::set_block_number(System::block_number() + 1);
+		Erc20::on_initialize(System::block_number());
+	}
+}
+
+#[test]
+fn test_scheduled_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(1),
+			2,
+			10.into(),
+			3,
+			2,
+			3,
+			FailurePolicy::Cancel
+		));
+		assert_eq!(Erc20::scheduled_transfers_count(1), 1);
+
+		run_to_block(2);
+		assert_eq!(Erc20::balance_of(2), Some(90.into()));
+		run_to_block(3);
+		assert_eq!(Erc20::balance_of(2), Some(100.into()));
+		run_to_block(4);
+		assert_eq!(Erc20::balance_of(2), Some(100.into()));
+		run_to_block(7);
+		assert_eq!(Erc20::balance_of(1), Some(80.into()));
+		assert_eq!(Erc20::balance_of(2), Some(120.into()));
+
+		// all the payments were made
+		assert_eq!(Erc20::scheduled_transfer(0), None);
+		assert_eq!(Erc20::scheduled_transfers_count(1), 0);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::TransferScheduled {
+				id: 0,
+				from: 1,
+				to: 2,
+				amount: 10.into(),
+				start: 3,
+				period: 2,
+				count: 3
+			},
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
+			crate::Event::ScheduledTransferExecuted { id: 0 },
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
+			crate::Event::ScheduledTransferExecuted { id: 0 },
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
+			crate::Event::ScheduledTransferExecuted { id: 0 },
+		);
+	});
+}
+
+#[test]
+fn test_scheduled_transfer_failures() {
+	new_test_ext().execute_with(|| {
+		// accounts 4, 5 and 6 don't have any tokens
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(4),
+			1,
+			10.into(),
+			3,
+			3,
+			2,
+			FailurePolicy::Skip
+		));
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(5),
+			1,
+			10.into(),
+			4,
+			1,
+			1,
+			FailurePolicy::Retry
+		));
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(6),
+			1,
+			10.into(),
+			7,
+			1,
+			1,
+			FailurePolicy::Cancel
+		));
+
+		run_to_block(4);
+		assert_ok!(Erc20::transfer(Origin::signed(2), 5, 10.into()));
+		run_to_block(7);
+
+		assert_eq!(Erc20::balance_of(1), Some(120.into()));
+		assert_eq!(Erc20::scheduled_transfer(0), None);
+		assert_eq!(Erc20::scheduled_transfer(1), None);
+		assert_eq!(Erc20::scheduled_transfer(2), None);
+
+		let failed = |id| crate::Event::ScheduledTransferFailed {
+			id,
+			error: Error::<Test>::InsufficientFunds.into(),
+		};
+		let scheduled = |id, from, start, period, count| crate::Event::TransferScheduled {
+			id,
+			from,
+			to: 1,
+			amount: 10.into(),
+			start,
+			period,
+			count,
+		};
+		assert_erc20_events!(
+			Test,
+			scheduled(0, 4, 3, 3, 2),
+			scheduled(1, 5, 4, 1, 1),
+			scheduled(2, 6, 7, 1, 1),
+			// block 3
+			failed(0),
+			// block 4
+			failed(1),
+			crate::Event::Transfer { from: 2, to: 5, amount: 10.into() },
+			// block 5: the failed payment is retried
+			crate::Event::Transfer { from: 5, to: 1, amount: 10.into() },
+			crate::Event::ScheduledTransferExecuted { id: 1 },
+			// block 6: the second payment fails as well, the skipped one is not retried
+			failed(0),
+			// block 7
+			failed(2),
+			crate::Event::ScheduledTransferCancelled { id: 2 },
+		);
+	});
+}
+
+#[test]
+fn test_scheduled_transfer_weight_budget() {
+	new_test_ext().execute_with(|| {
+		for from in [1, 1, 2] {
+			assert_ok!(Erc20::schedule_transfer(
+				Origin::signed(from),
+				0,
+				10.into(),
+				3,
+				1,
+				1,
+				FailurePolicy::Cancel
+			));
+		}
+
+		// only two payments fit into a block in the mock runtime
+		run_to_block(3);
+		assert_eq!(Erc20::balance_of(0), Some(20.into()));
+		run_to_block(4);
+		assert_eq!(Erc20::balance_of(0), Some(30.into()));
+	});
+}
+
+#[test]
+fn test_scheduled_transfer_errors() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Erc20::schedule_transfer(Origin::signed(1), 2, 10.into(), 1, 1, 1, FailurePolicy::Skip),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_noop!(
+			Erc20::schedule_transfer(Origin::signed(1), 2, 10.into(), 3, 0, 1, FailurePolicy::Skip),
+			Error::<Test>::InvalidSchedule
+		);
+		assert_noop!(
+			Erc20::schedule_transfer(Origin::signed(1), 2, 10.into(), 3, 1, 0, FailurePolicy::Skip),
+			Error::<Test>::InvalidSchedule
+		);
+
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(1),
+			2,
+			10.into(),
+			3,
+			1,
+			1,
+			FailurePolicy::Skip
+		));
+		assert_ok!(Erc20::schedule_transfer(
+			Origin::signed(1),
+			2,
+			10.into(),
+			3,
+			1,
+			1,
+			FailurePolicy::Skip
+		));
+		assert_noop!(
+			Erc20::schedule_transfer(Origin::signed(1), 2, 10.into(), 3, 1, 1, FailurePolicy::Skip),
+			Error::<Test>::TooManyScheduledTransfers
+		);
+
+		assert_noop!(
+			Erc20::cancel_scheduled_transfer(Origin::signed(2), 0),
+			Error::<Test>::NotScheduledTransferPayer
+		);
+		assert_ok!(Erc20::cancel_scheduled_transfer(Origin::signed(1), 0));
+		assert_noop!(
+			Erc20::cancel_scheduled_transfer(Origin::signed(1), 0),
+			Error::<Test>::UnknownScheduledTransfer
+		);
+
+		// the cancelled transfer is not executed
+		run_to_block(3);
+		assert_eq!(Erc20::balance_of(2), Some(100.into()));
+	});
+}
+
+#[test]
+fn test_multisig_transfer() {
+	new_test_ext().execute_with(|| {
+		let multisig = Erc20::multisig_account_id(&[1, 2, 3], 2);
+
+		assert_ok!(Erc20::create_multisig_account(Origin::signed(0), vec![3, 1, 2], 2));
+		assert_ok!(Erc20::transfer(Origin::signed(1), multisig, 50.into()));
+
+		assert_ok!(Erc20::propose_transfer(Origin::signed(1), multisig, 0, 20.into()));
+		assert_noop!(
+			Erc20::execute_proposal(Origin::signed(1), 0),
+			Error::<Test>::NotEnoughApprovals
 		);
+		assert_ok!(Erc20::approve_proposal(Origin::signed(2), 0));
+		assert_ok!(Erc20::execute_proposal(Origin::signed(3), 0));
+
+		assert_eq!(Erc20::balance_of(0), Some(20.into()));
+		assert_eq!(Erc20::balance_of(multisig), Some(30.into()));
+		assert_eq!(Erc20::proposal(0), None);
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::MultisigCreated {
+				account: multisig,
+				signatories: vec![1, 2, 3],
+				threshold: 2
+			},
+			crate::Event::Transfer { from: 1, to: multisig, amount: 50.into() },
+			crate::Event::ProposalCreated {
+				id: 0,
+				multisig,
+				to: 0,
+				amount: 20.into(),
+				expires_at: 11
+			},
+			crate::Event::ProposalApproved { id: 0, signatory: 1, approvals: 1 },
+			crate::Event::ProposalApproved { id: 0, signatory: 2, approvals: 2 },
+			crate::Event::Transfer { from: multisig, to: 0, amount: 20.into() },
+			crate::Event::ProposalExecuted { id: 0 },
+		);
+	});
+}
+
+#[test]
+fn test_multisig_account_id() {
+	new_test_ext().execute_with(|| {
+		let multisig = Erc20::multisig_account_id(&[1, 2, 3], 2);
+
+		assert_eq!(Erc20::multisig_account_id(&[3, 2, 1], 2), multisig);
+		assert_ne!(Erc20::multisig_account_id(&[1, 2, 3], 3), multisig);
+		assert_ne!(Erc20::multisig_account_id(&[1, 2], 2), multisig);
+	});
+}
+
+#[test]
+fn test_multisig_weights() {
+	let create = |signatories: Vec<u64>| {
+		crate::Call::<Test>::create_multisig_account { signatories, threshold: 1 }
+			.get_dispatch_info()
+			.weight
+	};
+
+	// the signatories are sorted and stored, the approvals of a proposal are searched
+	assert_eq!(create(vec![1, 2, 3]) - create(vec![1]), crate::signatories_weight(2));
+	assert!(
+		crate::Call::<Test>::approve_proposal { id: 0 }.get_dispatch_info().weight >=
+			crate::signatories_weight(<Test as crate::Config>::MaxSignatories::get().into())
+	);
+}
+
+#[test]
+fn test_multisig_proposal_expiry() {
+	new_test_ext().execute_with(|| {
+		let multisig = Erc20::multisig_account_id(&[1, 2], 2);
+
+		assert_ok!(Erc20::create_multisig_account(Origin::signed(1), vec![1, 2], 2));
+		assert_ok!(Erc20::transfer(Origin::signed(1), multisig, 50.into()));
+		assert_ok!(Erc20::propose_transfer(Origin::signed(1), multisig, 0, 20.into()));
+
+		assert_noop!(
+			Erc20::remove_expired_proposal(Origin::signed(0), 0),
+			Error::<Test>::ProposalNotExpired
+		);
+
+		System::set_block_number(11);
+
+		assert_noop!(Erc20::approve_proposal(Origin::signed(2), 0), Error::<Test>::ProposalExpired);
+		assert_ok!(Erc20::remove_expired_proposal(Origin::signed(0), 0));
+		assert_eq!(Erc20::proposal(0), None);
+		assert_eq!(Erc20::balance_of(multisig), Some(50.into()));
 	});
 }
+
+#[test]
+fn test_multisig_errors() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(1), vec![], 1),
+			Error::<Test>::InvalidSignatoriesCount
+		);
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(1), vec![1, 2, 3, 4], 2),
+			Error::<Test>::InvalidSignatoriesCount
+		);
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(1), vec![1, 2], 0),
+			Error::<Test>::InvalidThreshold
+		);
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(1), vec![1, 2], 3),
+			Error::<Test>::InvalidThreshold
+		);
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(1), vec![1, 2, 1], 2),
+			Error::<Test>::DuplicateSignatories
+		);
+
+		let multisig = Erc20::multisig_account_id(&[1, 2], 1);
+		assert_ok!(Erc20::create_multisig_account(Origin::signed(1), vec![1, 2], 1));
+		assert_noop!(
+			Erc20::create_multisig_account(Origin::signed(2), vec![2, 1], 1),
+			Error::<Test>::MultisigAlreadyExists
+		);
+
+		assert_noop!(
+			Erc20::propose_transfer(Origin::signed(1), 3, 0, 20.into()),
+			Error::<Test>::UnknownMultisig
+		);
+		assert_noop!(
+			Erc20::propose_transfer(Origin::signed(3), multisig, 0, 20.into()),
+			Error::<Test>::NotSignatory
+		);
+
+		assert_ok!(Erc20::propose_transfer(Origin::signed(1), multisig, 0, 20.into()));
+
+		assert_noop!(Erc20::approve_proposal(Origin::signed(1), 0), Error::<Test>::AlreadyApproved);
+		assert_noop!(Erc20::approve_proposal(Origin::signed(3), 0), Error::<Test>::NotSignatory);
+		assert_noop!(Erc20::approve_proposal(Origin::signed(1), 1), Error::<Test>::UnknownProposal);
+		// the multisig account doesn't have any tokens
+		assert_noop!(
+			Erc20::execute_proposal(Origin::signed(2), 0),
+			Error::<Test>::InsufficientFunds
+		);
+	});
+}
+
+#[test]
+fn test_simulate_transfer() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Erc20::simulate_transfer(1, 2, 10.into(), None),
+			Ok(TransferSimulation {
+				from_balance: 100.into(),
+				to_balance: 100.into(),
+				allowance: None,
+				events: vec![crate::Event::Transfer { from: 1, to: 2, amount: 10.into() }],
+			})
+		);
+		assert_eq!(
+			Erc20::simulate_transfer(1, 2, 10.into(), Some(4)),
+			Err(Error::<Test>::InsufficientAllowance.into())
+		);
+
+		assert_ok!(Erc20::approve(Origin::signed(1), 4, 20.into()));
+		assert_eq!(
+			Erc20::simulate_transfer(1, 2, 15.into(), Some(4)),
+			Ok(TransferSimulation {
+				from_balance: 95.into(),
+				to_balance: 105.into(),
+				allowance: Some(5.into()),
+				events: vec![
+					crate::Event::Transfer { from: 1, to: 2, amount: 15.into() },
+					crate::Event::Approval { owner: 1, spender: 4, amount: 5.into() },
+				],
+			})
+		);
+		assert_eq!(
+			Erc20::simulate_transfer(2, 1, 100.into(), None),
+			Err(Error::<Test>::InsufficientFunds.into())
+		);
+
+		// nothing was actually transferred
+		assert_eq!(Erc20::balance_of(1), Some(110.into()));
+		assert_eq!(Erc20::balance_of(2), Some(90.into()));
+		assert_eq!(Erc20::allowance(1, 4), Some(20.into()));
+		assert_erc20_events!(
+			Test,
+			crate::Event::Approval { owner: 1, spender: 4, amount: 20.into() }
+		);
+	});
+}
+
+fn claim_message(dest: u64) -> Vec<u8> {
+	[ClaimPrefix::get(), &dest.encode()[..]].concat()
+}
+
+fn account_claim(owner: u64, dest: u64) -> ClaimSignature<u64, TestSignature> {
+	ClaimSignature::Account { owner, signature: TestSignature(owner, claim_message(dest)) }
+}
+
+fn eth_secret(seed: &[u8]) -> libsecp256k1::SecretKey {
+	libsecp256k1::SecretKey::parse(&keccak_256(seed)).unwrap()
+}
+
+fn eth_address(secret: &libsecp256k1::SecretKey) -> EthereumAddress {
+	let public = libsecp256k1::PublicKey::from_secret_key(secret).serialize();
+	let mut address = [0; 20];
+	address.copy_from_slice(&keccak_256(&public[1..])[12..]);
+	EthereumAddress(address)
+}
+
+/// Signature of `prefix` followed by the hex-encoded `data`, as made by `eth_sign`
+fn eth_sign(secret: &libsecp256k1::SecretKey, prefix: &[u8], data: &[u8]) -> EcdsaSignature {
+	let payload = format!("{}{}", std::str::from_utf8(prefix).unwrap(), HexDisplay::from(&data));
+	let message = format!("\x19Ethereum Signed Message:\n{}{}", payload.len(), payload);
+
+	let (signature, recovery_id) =
+		libsecp256k1::sign(&libsecp256k1::Message::parse(&keccak_256(message.as_bytes())), secret);
+	let mut bytes = [0; 65];
+	bytes[..64].copy_from_slice(&signature.serialize());
+	bytes[64] = recovery_id.serialize();
+	EcdsaSignature(bytes)
+}
+
+/// Signature of the claim to `dest`
+fn eth_claim(secret: &libsecp256k1::SecretKey, dest: u64) -> ClaimSignature<u64, TestSignature> {
+	ClaimSignature::Ethereum(eth_sign(secret, ClaimPrefix::get(), &dest.encode()))
+}
+
+fn validate_claim(dest: u64, signature: ClaimSignature<u64, TestSignature>) -> TransactionValidity {
+	Erc20::validate_unsigned(TransactionSource::External, &crate::Call::claim { dest, signature })
+}
+
+#[test]
+fn test_claim_account() {
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 10.into())])
+		.with_claims(vec![(ClaimOwner::Account(5), 100.into())])
+		.build()
+		.execute_with(|| {
+			assert_eq!(Erc20::claims(ClaimOwner::Account(5)), Some(100.into()));
+
+			assert_ok!(Erc20::claim(Origin::none(), 7, account_claim(5, 7)));
+
+			assert_eq!(Erc20::balance_of(7), Some(100.into()));
+			assert_eq!(Erc20::balance_of(5), None);
+			assert_eq!(Erc20::total_supply(), Some(110.into()));
+			assert_eq!(Erc20::claims(ClaimOwner::Account(5)), None);
+			assert_erc20_events!(
+				Test,
+				crate::Event::Mint { account: 7, amount: 100.into() },
+				crate::Event::Claimed {
+					owner: ClaimOwner::Account(5),
+					dest: 7,
+					amount: 100.into()
+				},
+			);
+			assert_supply_consistent::<Test>();
+
+			// the allocation can be claimed only once
+			assert_noop!(
+				Erc20::claim(Origin::none(), 7, account_claim(5, 7)),
+				Error::<Test>::NoClaim
+			);
+		});
+}
+
+#[test]
+fn test_claim_ethereum() {
+	let secret = eth_secret(b"alice");
+	let address = eth_address(&secret);
+
+	ExtBuilder::<Test>::default()
+		.with_claims(vec![(ClaimOwner::Ethereum(address), 50.into())])
+		.build()
+		.execute_with(|| {
+			assert_ok!(Erc20::claim(Origin::none(), 3, eth_claim(&secret, 3)));
+
+			assert_eq!(Erc20::balance_of(3), Some(50.into()));
+			assert_eq!(Erc20::total_supply(), Some(50.into()));
+			assert_eq!(Erc20::claims(ClaimOwner::Ethereum(address)), None);
+			assert_erc20_events!(
+				Test,
+				crate::Event::Mint { account: 3, amount: 50.into() },
+				crate::Event::Claimed {
+					owner: ClaimOwner::Ethereum(address),
+					dest: 3,
+					amount: 50.into()
+				},
+			);
+		});
+}
+
+#[test]
+fn test_claim_errors() {
+	let secret = eth_secret(b"alice");
+
+	ExtBuilder::<Test>::default()
+		.with_claims(vec![
+			(ClaimOwner::Account(5), 100.into()),
+			(ClaimOwner::Ethereum(eth_address(&secret)), 50.into()),
+		])
+		.build()
+		.execute_with(|| {
+			assert_noop!(Erc20::claim(Origin::signed(5), 7, account_claim(5, 7)), BadOrigin);
+			// signed for another destination
+			assert_noop!(
+				Erc20::claim(Origin::none(), 7, account_claim(5, 8)),
+				Error::<Test>::InvalidClaimSignature
+			);
+			// signed for another destination, which recovers another address (without allocation)
+			assert_noop!(
+				Erc20::claim(Origin::none(), 7, eth_claim(&secret, 8)),
+				Error::<Test>::NoClaim
+			);
+			assert_noop!(
+				Erc20::claim(Origin::none(), 7, account_claim(6, 7)),
+				Error::<Test>::NoClaim
+			);
+		});
+}
+
+#[test]
+fn test_claim_validate_unsigned() {
+	let secret = eth_secret(b"alice");
+
+	ExtBuilder::<Test>::default()
+		.with_claims(vec![
+			(ClaimOwner::Account(5), 100.into()),
+			(ClaimOwner::Ethereum(eth_address(&secret)), 50.into()),
+		])
+		.build()
+		.execute_with(|| {
+			// the allocation is the tag, so that two claims of it can't be included
+			assert_eq!(
+				validate_claim(7, account_claim(5, 7)),
+				Ok(ValidTransaction {
+					priority: 100,
+					requires: vec![],
+					provides: vec![("Erc20Claim", ClaimOwner::<u64>::Account(5)).encode()],
+					longevity: 64,
+					propagate: true,
+				})
+			);
+			assert_eq!(
+				validate_claim(9, eth_claim(&secret, 9)).map(|valid| valid.provides),
+				Ok(
+					vec![("Erc20Claim", ClaimOwner::<u64>::Ethereum(eth_address(&secret))).encode()]
+				)
+			);
+
+			assert_eq!(
+				validate_claim(7, account_claim(5, 8)),
+				Err(InvalidTransaction::BadProof.into())
+			);
+			assert_eq!(
+				validate_claim(7, account_claim(6, 7)),
+				Err(InvalidTransaction::Custom(NO_CLAIM).into())
+			);
+			assert_eq!(
+				Erc20::validate_unsigned(
+					TransactionSource::External,
+					&crate::Call::transfer { to: 2, amount: 1.into() }
+				),
+				Err(InvalidTransaction::Call.into())
+			);
+
+			// claimed allocations can't be claimed again, so the transaction is dropped
+			assert_ok!(Erc20::claim(Origin::none(), 7, account_claim(5, 7)));
+			assert_eq!(
+				validate_claim(7, account_claim(5, 7)),
+				Err(InvalidTransaction::Custom(NO_CLAIM).into())
+			);
+		});
+}
+
+/// Merkle tree of the airdrop of `amount` tokens to `account` for each of `entries`, hashed like
+/// `kind` does. Returns the root and the proof of each leaf
+fn airdrop_tree(
+	kind: fn(H256) -> MerkleRoot,
+	entries: &[(u64, U256)],
+) -> (MerkleRoot, Vec<Vec<H256>>) {
+	let tree = kind(H256::zero());
+	let mut level = entries
+		.iter()
+		.enumerate()
+		.map(|(index, (account, amount))| tree.leaf(index as u32, account, *amount))
+		.collect::<Vec<_>>();
+	let mut positions = (0..entries.len()).collect::<Vec<_>>();
+	let mut proofs = vec![vec![]; entries.len()];
+
+	while level.len() > 1 {
+		for (proof, position) in proofs.iter_mut().zip(&mut positions) {
+			// the last node of an odd level is promoted as is
+			if let Some(sibling) = level.get(*position ^ 1) {
+				proof.push(*sibling);
+			}
+			*position /= 2;
+		}
+		level = level
+			.chunks(2)
+			.map(|pair| if let [a, b] = pair { tree.node(*a, *b) } else { pair[0] })
+			.collect();
+	}
+
+	(kind(level[0]), proofs)
+}
+
+fn airdrop_entries() -> Vec<(u64, U256)> {
+	vec![(2, 10.into()), (4, 20.into()), (5, 30.into())]
+}
+
+#[test]
+fn test_airdrop_funded() {
+	let entries = airdrop_entries();
+	let (root, proofs) = airdrop_tree(MerkleRoot::Blake2_256, &entries);
+
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 100.into())])
+		.build()
+		.execute_with(|| {
+			let pallet = Erc20::account_id();
+
+			assert_ok!(Erc20::create_airdrop(Origin::signed(1), root, 60.into(), 10));
+			assert_eq!(Erc20::balance_of(1), Some(40.into()));
+			assert_eq!(Erc20::balance_of(pallet), Some(60.into()));
+
+			// anyone can submit the claims
+			assert_ok!(Erc20::claim_airdrop(
+				Origin::signed(9),
+				0,
+				1,
+				4,
+				20.into(),
+				proofs[1].clone()
+			));
+			assert_ok!(Erc20::claim_airdrop(
+				Origin::signed(5),
+				0,
+				2,
+				5,
+				30.into(),
+				proofs[2].clone()
+			));
+			assert_eq!(Erc20::balance_of(4), Some(20.into()));
+			assert_eq!(Erc20::balance_of(5), Some(30.into()));
+			assert_eq!(Erc20::airdrop(0).map(|airdrop| airdrop.remaining), Some(10.into()));
+
+			System::set_block_number(10);
+			assert_noop!(
+				Erc20::claim_airdrop(Origin::signed(2), 0, 0, 2, 10.into(), proofs[0].clone()),
+				Error::<Test>::AirdropExpired
+			);
+			assert_ok!(Erc20::sweep_airdrop(Origin::signed(9), 0));
+
+			// the unclaimed tokens are returned to the funder
+			assert_eq!(Erc20::balance_of(1), Some(50.into()));
+			assert_eq!(Erc20::balance_of(pallet), Some(0.into()));
+			assert_eq!(Erc20::airdrop(0), None);
+			assert_eq!(Erc20::total_supply(), Some(100.into()));
+			assert_erc20_events!(
+				Test,
+				crate::Event::Transfer { from: 1, to: pallet, amount: 60.into() },
+				crate::Event::AirdropCreated {
+					id: 0,
+					funder: Some(1),
+					merkle_root: root,
+					total: 60.into(),
+					expires_at: 10
+				},
+				crate::Event::Transfer { from: pallet, to: 4, amount: 20.into() },
+				crate::Event::AirdropClaimed { id: 0, index: 1, account: 4, amount: 20.into() },
+				crate::Event::Transfer { from: pallet, to: 5, amount: 30.into() },
+				crate::Event::AirdropClaimed { id: 0, index: 2, account: 5, amount: 30.into() },
+				crate::Event::Transfer { from: pallet, to: 1, amount: 10.into() },
+				crate::Event::AirdropSwept { id: 0, amount: 10.into() },
+			);
+			assert_supply_consistent::<Test>();
+		});
+}
+
+#[test]
+fn test_airdrop_minted() {
+	let entries = airdrop_entries();
+	let (root, proofs) = airdrop_tree(MerkleRoot::Keccak256, &entries);
+
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 100.into())])
+		.build()
+		.execute_with(|| {
+			assert_ok!(Erc20::create_airdrop(Origin::root(), root, 60.into(), 10));
+			assert_eq!(Erc20::total_supply(), Some(160.into()));
+			assert_eq!(
+				Erc20::airdrop(0).map(|airdrop| airdrop.funder),
+				Some(None),
+				"the tokens were minted"
+			);
+
+			assert_ok!(Erc20::claim_airdrop(
+				Origin::signed(1),
+				0,
+				0,
+				2,
+				10.into(),
+				proofs[0].clone()
+			));
+			assert_eq!(Erc20::balance_of(2), Some(10.into()));
+
+			System::set_block_number(10);
+			assert_ok!(Erc20::sweep_airdrop(Origin::signed(1), 0));
+
+			// the unclaimed tokens are burnt
+			assert_eq!(Erc20::total_supply(), Some(110.into()));
+			assert_eq!(Erc20::balance_of(1), Some(100.into()));
+			assert_supply_consistent::<Test>();
+		});
+}
+
+#[test]
+fn test_airdrop_claims_bitmap() {
+	// enough leaves to span several words of the bitmap
+	let entries = (0..300).map(|account| (account, U256::from(1))).collect::<Vec<_>>();
+	let (root, proofs) = airdrop_tree(MerkleRoot::Blake2_256, &entries);
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::create_airdrop(Origin::signed(1), root, 100.into(), 10));
+
+		for index in [0, 127, 128, 255, 299] {
+			let proof = proofs[index as usize].clone();
+			assert_ok!(Erc20::claim_airdrop(
+				Origin::signed(1),
+				0,
+				index,
+				index.into(),
+				1.into(),
+				proof
+			));
+		}
+		for index in [0, 127, 128, 255, 299] {
+			let proof = proofs[index as usize].clone();
+			assert_noop!(
+				Erc20::claim_airdrop(Origin::signed(1), 0, index, index.into(), 1.into(), proof),
+				Error::<Test>::AirdropAlreadyClaimed
+			);
+		}
+		assert_ok!(Erc20::claim_airdrop(Origin::signed(1), 0, 1, 1, 1.into(), proofs[1].clone()));
+
+		assert_eq!(Erc20::airdrop(0).map(|airdrop| airdrop.remaining), Some(94.into()));
+	});
+}
+
+#[test]
+fn test_airdrop_sweep_bitmap() {
+	// claims in the three words of the bitmap, which take two sweeps to remove
+	let entries = (0..300).map(|account| (account, U256::from(1))).collect::<Vec<_>>();
+	let (root, proofs) = airdrop_tree(MerkleRoot::Blake2_256, &entries);
+
+	let mut ext = new_test_ext();
+	ext.execute_with(|| {
+		assert_ok!(Erc20::create_airdrop(Origin::signed(1), root, 100.into(), 10));
+		for index in [0, 128, 256] {
+			let proof = proofs[index as usize].clone();
+			assert_ok!(Erc20::claim_airdrop(
+				Origin::signed(1),
+				0,
+				index,
+				index.into(),
+				1.into(),
+				proof
+			));
+		}
+	});
+	// the limit only applies to the bitmap stored in the backend
+	ext.commit_all().unwrap();
+
+	ext.execute_with(|| {
+		System::set_block_number(10);
+		assert_ok!(Erc20::sweep_airdrop(Origin::signed(9), 0));
+		assert_eq!(Erc20::airdrop(0), None);
+		assert_eq!(Erc20::balance_of(1), Some(107.into()));
+		assert!(AirdropsBeingSwept::<Test>::contains_key(0));
+		assert_eq!(AirdropClaimedBitmap::<Test>::iter_prefix(0).count(), 1);
+
+		// the next call removes the rest of the bitmap, without paying anything out again
+		assert_ok!(Erc20::sweep_airdrop(Origin::signed(9), 0));
+		assert_eq!(Erc20::balance_of(1), Some(107.into()));
+		assert!(!AirdropsBeingSwept::<Test>::contains_key(0));
+		assert_eq!(AirdropClaimedBitmap::<Test>::iter_prefix(0).count(), 0);
+
+		assert_noop!(Erc20::sweep_airdrop(Origin::signed(9), 0), Error::<Test>::UnknownAirdrop);
+		assert_supply_consistent::<Test>();
+	});
+}
+
+#[test]
+fn test_airdrop_errors() {
+	let entries = airdrop_entries();
+	let (root, proofs) = airdrop_tree(MerkleRoot::Blake2_256, &entries);
+
+	new_test_ext().execute_with(|| {
+		assert_noop!(Erc20::create_airdrop(Origin::none(), root, 60.into(), 10), BadOrigin);
+		assert_noop!(
+			Erc20::create_airdrop(Origin::signed(1), root, 60.into(), 1),
+			Error::<Test>::AirdropExpiryInPast
+		);
+		assert_noop!(
+			Erc20::create_airdrop(Origin::signed(1), root, 200.into(), 10),
+			Error::<Test>::InsufficientFunds
+		);
+		assert_noop!(
+			Erc20::claim_airdrop(Origin::signed(1), 0, 0, 2, 10.into(), proofs[0].clone()),
+			Error::<Test>::UnknownAirdrop
+		);
+		assert_noop!(Erc20::sweep_airdrop(Origin::signed(1), 0), Error::<Test>::UnknownAirdrop);
+
+		// the tree distributes 60 tokens, but only 25 were locked
+		assert_ok!(Erc20::create_airdrop(Origin::signed(1), root, 25.into(), 10));
+
+		// wrong amount, account, index, proof and hash function
+		for (index, account, amount, proof) in [
+			(0, 2, 11, proofs[0].clone()),
+			(0, 3, 10, proofs[0].clone()),
+			(1, 2, 10, proofs[0].clone()),
+			(0, 2, 10, proofs[1].clone()),
+			(0, 2, 10, vec![]),
+		] {
+			assert_noop!(
+				Erc20::claim_airdrop(Origin::signed(1), 0, index, account, amount.into(), proof),
+				Error::<Test>::InvalidMerkleProof
+			);
+		}
+		assert_ok!(Erc20::create_airdrop(
+			Origin::signed(1),
+			MerkleRoot::Keccak256(root.root()),
+			10.into(),
+			10
+		));
+		assert_noop!(
+			Erc20::claim_airdrop(Origin::signed(1), 1, 0, 2, 10.into(), proofs[0].clone()),
+			Error::<Test>::InvalidMerkleProof
+		);
+
+		assert_ok!(Erc20::claim_airdrop(Origin::signed(1), 0, 0, 2, 10.into(), proofs[0].clone()));
+		assert_noop!(
+			Erc20::claim_airdrop(Origin::signed(1), 0, 1, 4, 20.into(), proofs[1].clone()),
+			Error::<Test>::AirdropExhausted
+		);
+
+		assert_noop!(Erc20::sweep_airdrop(Origin::signed(1), 0), Error::<Test>::AirdropNotExpired);
+	});
+}
+
+fn eth_link(secret: &libsecp256k1::SecretKey, account: u64) -> EcdsaSignature {
+	eth_sign(secret, EthereumPrefix::get(), &EthereumPayload::Link { account }.encode())
+}
+
+/// `ethereum_transact` of `call` signed by `secret`
+fn eth_transact(
+	secret: &libsecp256k1::SecretKey,
+	nonce: u64,
+	call: EthereumCall<u64>,
+) -> crate::Call<Test> {
+	let payload = EthereumPayload::Transaction { nonce, call: call.clone() };
+	let signature = eth_sign(secret, EthereumPrefix::get(), &payload.encode());
+	crate::Call::ethereum_transact { nonce, call, signature }
+}
+
+fn dispatch_unsigned(call: crate::Call<Test>) -> DispatchResult {
+	call.dispatch_bypass_filter(Origin::none()).map(|_| ()).map_err(|e| e.error)
+}
+
+#[test]
+fn test_ethereum_link() {
+	let (alice, bob) = (eth_secret(b"alice"), eth_secret(b"bob"));
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+
+		assert_eq!(Erc20::ethereum_account(eth_address(&alice)), Some(1));
+		assert_eq!(Erc20::linked_ethereum_address(1), Some(eth_address(&alice)));
+		assert_eq!(Erc20::ethereum_address(&1), eth_address(&alice));
+		// unlinked accounts are represented by their first 20 encoded bytes
+		let mut two = [0; 20];
+		two[0] = 2;
+		assert_eq!(Erc20::ethereum_address(&2), EthereumAddress(two));
+
+		assert_noop!(
+			Erc20::link_ethereum_address(Origin::signed(1), eth_link(&bob, 1)),
+			Error::<Test>::AccountAlreadyLinked
+		);
+		assert_noop!(
+			Erc20::link_ethereum_address(Origin::signed(2), eth_link(&alice, 2)),
+			Error::<Test>::EthereumAddressAlreadyLinked
+		);
+		assert_noop!(
+			Erc20::link_ethereum_address(Origin::signed(2), EcdsaSignature([0; 65])),
+			Error::<Test>::InvalidEthereumSignature
+		);
+
+		assert_ok!(Erc20::unlink_ethereum_address(Origin::signed(1)));
+		assert_noop!(
+			Erc20::unlink_ethereum_address(Origin::signed(1)),
+			Error::<Test>::AccountNotLinked
+		);
+		assert_eq!(Erc20::ethereum_account(eth_address(&alice)), None);
+
+		// the address can now be linked to another account
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(2), eth_link(&alice, 2)));
+		assert_erc20_events!(
+			Test,
+			crate::Event::EthereumAddressLinked { account: 1, address: eth_address(&alice) },
+			crate::Event::EthereumAddressUnlinked { account: 1, address: eth_address(&alice) },
+			crate::Event::EthereumAddressLinked { account: 2, address: eth_address(&alice) },
+		);
+	});
+}
+
+#[test]
+fn test_ethereum_transact() {
+	let alice = eth_secret(b"alice");
+	let address = eth_address(&alice);
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+		assert_ok!(Erc20::approve(Origin::signed(2), 1, 30.into()));
+		System::reset_events();
+
+		let transfer = eth_transact(&alice, 0, EthereumCall::Transfer { to: 4, amount: 10.into() });
+		assert_ok!(dispatch_unsigned(transfer.clone()));
+		assert_eq!(Erc20::balance_of(1), Some(100.into()));
+		assert_eq!(Erc20::balance_of(4), Some(10.into()));
+		assert_eq!(Erc20::ethereum_nonce(address), 1);
+
+		// replays are rejected
+		assert_noop!(dispatch_unsigned(transfer), Error::<Test>::InvalidEthereumNonce);
+
+		// a failing call uses the nonce, but its changes are reverted
+		assert_ok!(dispatch_unsigned(eth_transact(
+			&alice,
+			1,
+			EthereumCall::TransferFrom { from: 2, to: 4, amount: 31.into() }
+		)));
+		assert_eq!(Erc20::ethereum_nonce(address), 2);
+		assert_eq!(Erc20::balance_of(4), Some(10.into()));
+
+		assert_ok!(dispatch_unsigned(eth_transact(
+			&alice,
+			2,
+			EthereumCall::TransferFrom { from: 2, to: 4, amount: 30.into() }
+		)));
+		assert_ok!(dispatch_unsigned(eth_transact(
+			&alice,
+			3,
+			EthereumCall::Approve { spender: 5, amount: 7.into() }
+		)));
+		assert_eq!(Erc20::balance_of(4), Some(40.into()));
+		assert_eq!(Erc20::allowance(2, 1), Some(0.into()));
+		assert_eq!(Erc20::allowance(1, 5), Some(7.into()));
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::Transfer { from: 1, to: 4, amount: 10.into() },
+			crate::Event::EthereumTransactionExecuted { address, nonce: 0, result: Ok(()) },
+			crate::Event::EthereumTransactionExecuted {
+				address,
+				nonce: 1,
+				result: Err(Error::<Test>::InsufficientAllowance.into())
+			},
+			crate::Event::Transfer { from: 2, to: 4, amount: 30.into() },
+			crate::Event::Approval { owner: 2, spender: 1, amount: 0.into() },
+			crate::Event::EthereumTransactionExecuted { address, nonce: 2, result: Ok(()) },
+			crate::Event::Approval { owner: 1, spender: 5, amount: 7.into() },
+			crate::Event::EthereumTransactionExecuted { address, nonce: 3, result: Ok(()) },
+		);
+
+		// the nonce is kept when the address is unlinked
+		assert_ok!(Erc20::unlink_ethereum_address(Origin::signed(1)));
+		assert_noop!(
+			dispatch_unsigned(eth_transact(
+				&alice,
+				4,
+				EthereumCall::Transfer { to: 4, amount: 1.into() }
+			)),
+			Error::<Test>::UnlinkedEthereumAddress
+		);
+		assert_eq!(Erc20::ethereum_nonce(address), 4);
+		assert_noop!(
+			Erc20::ethereum_transact(
+				Origin::signed(1),
+				4,
+				EthereumCall::Transfer { to: 4, amount: 1.into() },
+				EcdsaSignature([0; 65])
+			),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn test_ethereum_validate_unsigned() {
+	let (alice, bob) = (eth_secret(b"alice"), eth_secret(b"bob"));
+	let address = eth_address(&alice);
+	let transfer = EthereumCall::Transfer { to: 4, amount: 10.into() };
+	let validate =
+		|call: crate::Call<Test>| Erc20::validate_unsigned(TransactionSource::External, &call);
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+		assert_ok!(dispatch_unsigned(eth_transact(&alice, 0, transfer.clone())));
+
+		// an address gets one transaction every `EthereumTransactionInterval` blocks
+		assert_eq!(
+			validate(eth_transact(&alice, 1, transfer.clone())),
+			Err(InvalidTransaction::Custom(RATE_LIMITED_ETHEREUM_ADDRESS).into())
+		);
+		System::set_block_number(3);
+
+		assert_eq!(
+			validate(eth_transact(&alice, 1, transfer.clone())),
+			Ok(ValidTransaction {
+				priority: 10,
+				requires: vec![],
+				provides: vec![("Erc20Ethereum", (address, 1u64)).encode()],
+				longevity: 64,
+				propagate: true,
+			})
+		);
+		// future transactions wait for the previous nonce
+		assert_eq!(
+			validate(eth_transact(&alice, 3, transfer.clone())).map(|valid| valid.requires),
+			Ok(vec![("Erc20Ethereum", (address, 2u64)).encode()])
+		);
+
+		assert_eq!(
+			validate(eth_transact(&alice, 0, transfer.clone())),
+			Err(InvalidTransaction::Stale.into())
+		);
+		assert_eq!(
+			validate(eth_transact(&bob, 0, transfer.clone())),
+			Err(InvalidTransaction::Custom(UNLINKED_ETHEREUM_ADDRESS).into())
+		);
+
+		// the transactions are free, so those whose call would fail are rejected
+		assert_eq!(
+			validate(eth_transact(&alice, 1, EthereumCall::Transfer { to: 4, amount: 101.into() })),
+			Err(InvalidTransaction::Custom(FAILING_ETHEREUM_CALL).into())
+		);
+		assert_eq!(
+			validate(eth_transact(
+				&alice,
+				1,
+				EthereumCall::TransferFrom { from: 2, to: 4, amount: 1.into() }
+			)),
+			Err(InvalidTransaction::Custom(FAILING_ETHEREUM_CALL).into())
+		);
+		// so are those whose call would change nothing
+		for call in [
+			EthereumCall::Transfer { to: 4, amount: 0.into() },
+			EthereumCall::TransferFrom { from: 2, to: 4, amount: 0.into() },
+			EthereumCall::Approve { spender: 5, amount: 0.into() },
+		] {
+			assert_eq!(
+				validate(eth_transact(&alice, 1, call)),
+				Err(InvalidTransaction::Custom(NO_OP_ETHEREUM_CALL).into())
+			);
+		}
+		// checking the call doesn't change anything
+		assert_eq!(Erc20::balance_of(1), Some(100.into()));
+		assert_eq!(Erc20::balance_of(4), Some(10.into()));
+		assert_eq!(Erc20::ethereum_nonce(address), 1);
+		assert_eq!(
+			validate(crate::Call::ethereum_transact {
+				nonce: 1,
+				call: transfer.clone(),
+				signature: EcdsaSignature([0; 65])
+			}),
+			Err(InvalidTransaction::BadProof.into())
+		);
+	});
+}
+
+#[test]
+fn test_ethereum_logs() {
+	let alice = eth_secret(b"alice");
+	let topic = |word: &str| word.parse::<H256>().unwrap();
+	let transfer_topic = topic("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef");
+	let approval_topic = topic("8c5be1e5ebec7d5bd14f71427d1e84f3dd0314c0f7b2291e5b200ac8c7c3b925");
+	let amount = |amount: u64| {
+		let mut data = vec![0; 32];
+		U256::from(amount).to_big_endian(&mut data);
+		data
+	};
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+		EthereumLogs::set(true);
+		System::reset_events();
+
+		let contract = Erc20::ethereum_contract_address();
+		let (one, two) = (eth_address(&alice).to_word(), Erc20::ethereum_address(&2).to_word());
+		let zero = H256::zero();
+
+		assert_ok!(Erc20::transfer(Origin::signed(1), 2, 10.into()));
+		assert_ok!(Erc20::approve(Origin::signed(2), 1, 5.into()));
+		assert_ok!(Erc20::mint(2, 3.into()));
+		assert_ok!(Erc20::burn(1, 4.into()));
+
+		assert_erc20_events!(
+			Test,
+			crate::Event::EthereumLog {
+				address: contract,
+				topics: vec![transfer_topic, one, two],
+				data: amount(10)
+			},
+			crate::Event::Transfer { from: 1, to: 2, amount: 10.into() },
+			crate::Event::EthereumLog {
+				address: contract,
+				topics: vec![approval_topic, two, one],
+				data: amount(5)
+			},
+			crate::Event::Approval { owner: 2, spender: 1, amount: 5.into() },
+			crate::Event::EthereumLog {
+				address: contract,
+				topics: vec![transfer_topic, zero, two],
+				data: amount(3)
+			},
+			crate::Event::Mint { account: 2, amount: 3.into() },
+			crate::Event::EthereumLog {
+				address: contract,
+				topics: vec![transfer_topic, one, zero],
+				data: amount(4)
+			},
+			crate::Event::Burn { account: 1, amount: 4.into() },
+		);
+	});
+}
+
+#[test]
+fn test_abi_selectors() {
+	let selector = |signature: &str| keccak_256(signature.as_bytes())[..4].to_vec();
+
+	assert_eq!(selector("name()"), abi::NAME);
+	assert_eq!(selector("symbol()"), abi::SYMBOL);
+	assert_eq!(selector("decimals()"), abi::DECIMALS);
+	assert_eq!(selector("totalSupply()"), abi::TOTAL_SUPPLY);
+	assert_eq!(selector("balanceOf(address)"), abi::BALANCE_OF);
+	assert_eq!(selector("allowance(address,address)"), abi::ALLOWANCE);
+	assert_eq!(selector("transfer(address,uint256)"), abi::TRANSFER);
+	assert_eq!(selector("approve(address,uint256)"), abi::APPROVE);
+	assert_eq!(selector("transferFrom(address,address,uint256)"), abi::TRANSFER_FROM);
+}
+
+#[test]
+fn test_abi_decode() {
+	let (alice, bob) = (EthereumAddress([0xaa; 20]), EthereumAddress([0xbb; 20]));
+	let functions = vec![
+		Erc20Function::Name,
+		Erc20Function::Symbol,
+		Erc20Function::Decimals,
+		Erc20Function::TotalSupply,
+		Erc20Function::BalanceOf { owner: alice },
+		Erc20Function::Allowance { owner: alice, spender: bob },
+		Erc20Function::Transfer { to: bob, amount: U256::max_value() },
+		Erc20Function::Approve { spender: bob, amount: 7.into() },
+		Erc20Function::TransferFrom { from: alice, to: bob, amount: 1.into() },
+	];
+	for function in functions {
+		assert_eq!(Erc20Function::decode(&function.encode()), Ok(function));
+	}
+
+	// transfer(0xbb..bb, 10), as encoded by web3 libraries
+	let transfer = sp_core::bytes::from_hex(concat!(
+		"a9059cbb",
+		"000000000000000000000000bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb",
+		"000000000000000000000000000000000000000000000000000000000000000a",
+	))
+	.unwrap();
+	assert_eq!(
+		Erc20Function::decode(&transfer),
+		Ok(Erc20Function::Transfer { to: bob, amount: 10.into() })
+	);
+	// trailing bytes are ignored
+	assert_eq!(
+		Erc20Function::decode(&[&transfer[..], &[1, 2, 3]].concat()),
+		Ok(Erc20Function::Transfer { to: bob, amount: 10.into() })
+	);
+
+	assert_eq!(Erc20Function::decode(&transfer[..3]), Err(AbiError::UnknownSelector));
+	assert_eq!(Erc20Function::decode(&[0xff; 68]), Err(AbiError::UnknownSelector));
+	assert_eq!(Erc20Function::decode(&transfer[..67]), Err(AbiError::InvalidArguments));
+	let mut dirty_address = transfer;
+	dirty_address[4] = 1;
+	assert_eq!(Erc20Function::decode(&dirty_address), Err(AbiError::InvalidArguments));
+}
+
+#[test]
+fn test_abi_encode_results() {
+	assert_eq!(abi::encode_bool(true), [&[0u8; 31][..], &[1][..]].concat());
+	assert_eq!(abi::encode_uint(258.into()), [&[0u8; 30][..], &[1, 2][..]].concat());
+	assert_eq!(
+		abi::encode_string("Meme Coin"),
+		[
+			&abi::encode_uint(32.into())[..],
+			&abi::encode_uint(9.into())[..],
+			&b"Meme Coin"[..],
+			&[0; 23][..]
+		]
+		.concat()
+	);
+	assert_eq!(abi::encode_string("").len(), 64);
+	assert_eq!(abi::encode_string(&"a".repeat(33)).len(), 128);
+}
+
+#[test]
+fn test_abi_call() {
+	let alice = eth_secret(b"alice");
+	let unlinked = EthereumAddress([0xbb; 20]);
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+		let one = eth_address(&alice);
+
+		assert_eq!(
+			Erc20::abi_call(&Erc20Function::Transfer { to: one, amount: 5.into() }.encode()),
+			Ok(crate::Call::transfer { to: 1, amount: 5.into() })
+		);
+		assert_eq!(
+			Erc20::abi_call(&Erc20Function::Approve { spender: one, amount: 5.into() }.encode()),
+			Ok(crate::Call::approve { spender: 1, amount: 5.into() })
+		);
+		assert_eq!(
+			Erc20::abi_call(
+				&Erc20Function::TransferFrom { from: one, to: one, amount: 5.into() }.encode()
+			),
+			Ok(crate::Call::transfer_from { from: 1, to: 1, amount: 5.into() })
+		);
+
+		assert_eq!(
+			Erc20::abi_call(&Erc20Function::Transfer { to: unlinked, amount: 5.into() }.encode()),
+			Err(Error::<Test>::UnlinkedEthereumAddress.into())
+		);
+		assert_eq!(
+			Erc20::abi_call(&Erc20Function::TotalSupply.encode()),
+			Err(Error::<Test>::AbiViewFunction.into())
+		);
+		assert_eq!(Erc20::abi_call(&[0xff; 4]), Err(Error::<Test>::UnknownAbiSelector.into()));
+		assert_eq!(Erc20::abi_call(&abi::TRANSFER), Err(Error::<Test>::InvalidAbiArguments.into()));
+	});
+}
+
+#[test]
+fn test_eth_call() {
+	let (alice, bob) = (eth_secret(b"alice"), eth_secret(b"bob"));
+	let unlinked = EthereumAddress([0xcc; 20]);
+	let uint = |value: u64| abi::encode_uint(value.into());
+
+	new_test_ext().execute_with(|| {
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(1), eth_link(&alice, 1)));
+		assert_ok!(Erc20::link_ethereum_address(Origin::signed(2), eth_link(&bob, 2)));
+		assert_ok!(Erc20::approve(Origin::signed(1), 2, 30.into()));
+		let (one, two) = (eth_address(&alice), eth_address(&bob));
+		let eth_call = |from, function: Erc20Function| Erc20::eth_call(from, &function.encode());
+
+		assert_eq!(eth_call(None, Erc20Function::Name), Ok(abi::encode_string("Meme Coin")));
+		assert_eq!(eth_call(None, Erc20Function::Symbol), Ok(abi::encode_string("MEM")));
+		assert_eq!(eth_call(None, Erc20Function::Decimals), Ok(uint(18)));
+		assert_eq!(eth_call(None, Erc20Function::TotalSupply), Ok(uint(200)));
+		assert_eq!(eth_call(None, Erc20Function::BalanceOf { owner: one }), Ok(uint(110)));
+		assert_eq!(eth_call(None, Erc20Function::BalanceOf { owner: unlinked }), Ok(uint(0)));
+		assert_eq!(
+			eth_call(None, Erc20Function::Allowance { owner: one, spender: two }),
+			Ok(uint(30))
+		);
+		assert_eq!(
+			eth_call(None, Erc20Function::Allowance { owner: one, spender: unlinked }),
+			Ok(uint(0))
+		);
+
+		// the functions changing the state are dry-run
+		assert_eq!(
+			eth_call(Some(one), Erc20Function::Transfer { to: two, amount: 10.into() }),
+			Ok(abi::encode_bool(true))
+		);
+		assert_eq!(
+			eth_call(
+				Some(two),
+				Erc20Function::TransferFrom { from: one, to: two, amount: 30.into() }
+			),
+			Ok(abi::encode_bool(true))
+		);
+		assert_eq!(
+			eth_call(
+				Some(two),
+				Erc20Function::TransferFrom { from: one, to: two, amount: 31.into() }
+			),
+			Err(Error::<Test>::InsufficientAllowance.into())
+		);
+		assert_eq!(
+			eth_call(Some(two), Erc20Function::Transfer { to: one, amount: 91.into() }),
+			Err(Error::<Test>::InsufficientFunds.into())
+		);
+		assert_eq!(
+			eth_call(None, Erc20Function::Approve { spender: two, amount: 1.into() }),
+			Err(Error::<Test>::UnlinkedEthereumAddress.into())
+		);
+		assert_eq!(
+			eth_call(Some(unlinked), Erc20Function::Approve { spender: two, amount: 1.into() }),
+			Err(Error::<Test>::UnlinkedEthereumAddress.into())
+		);
+		assert_eq!(Erc20::eth_call(None, &[]), Err(Error::<Test>::UnknownAbiSelector.into()));
+
+		assert_eq!(Erc20::balance_of(1), Some(110.into()));
+		assert_eq!(Erc20::balance_of(2), Some(90.into()));
+		assert_eq!(Erc20::allowance(1, 2), Some(30.into()));
+		assert_eq!(System::events().len(), 3);
+	});
+}
+
+#[test]
+fn test_convert_decimals() {
+	assert_eq!(convert_decimals(5.into(), 12, 18), Some(5_000_000.into()));
+	assert_eq!(convert_decimals(5_000_000.into(), 18, 12), Some(5.into()));
+	assert_eq!(convert_decimals(5_000_001.into(), 18, 12), None);
+	assert_eq!(convert_decimals(7.into(), 6, 6), Some(7.into()));
+	assert_eq!(convert_decimals(U256::max_value(), 0, 1), None);
+	assert_eq!(convert_decimals(1.into(), 0, 78), None);
+
+	new_test_ext().execute_with(|| {
+		let max = U256::from(u64::MAX) * 1_000_000;
+
+		assert_eq!(Erc20::wrapped_amount(u64::MAX), Some(max));
+		assert_eq!(Erc20::unwrapped_amount(max), Some(u64::MAX));
+		// more native tokens than the mock runtime's `u64` balances can hold
+		assert_eq!(Erc20::unwrapped_amount(max + 1_000_000), None);
+		assert_eq!(Erc20::unwrapped_amount(1.into()), None);
+	});
+}
+
+#[test]
+fn test_wrap() {
+	let pallet = Erc20::account_id();
+
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 10.into()), (2, 1_000_000_000.into())])
+		.build()
+		.execute_with(|| {
+			let _ = Balances::deposit_creating(&1, 1_000);
+			// the pallet account is endowed with the existential deposit at genesis
+			assert_eq!(Balances::free_balance(pallet), 5);
+
+			assert_ok!(Erc20::wrap(Origin::signed(1), 100));
+			assert_eq!(Balances::free_balance(1), 900);
+			assert_eq!(Balances::free_balance(pallet), 105);
+			assert_eq!(Erc20::balance_of(1), Some(100_000_010.into()));
+			assert_eq!(Erc20::wrapped_supply(), U256::from(100_000_000));
+
+			assert_ok!(Erc20::unwrap(Origin::signed(1), 50_000_000.into()));
+			assert_eq!(Balances::free_balance(1), 950);
+			assert_eq!(Balances::free_balance(pallet), 55);
+			assert_eq!(Erc20::balance_of(1), Some(50_000_010.into()));
+			assert_eq!(Erc20::wrapped_supply(), U256::from(50_000_000));
+			assert_supply_consistent::<Test>();
+			assert_wrapped_supply_consistent::<Test>();
+
+			// any holder can unwrap, up to the wrapped supply
+			assert_ok!(Erc20::unwrap(Origin::signed(2), 10_000_000.into()));
+			assert_eq!(Balances::free_balance(2), 10);
+			assert_noop!(
+				Erc20::unwrap(Origin::signed(2), 41_000_000.into()),
+				Error::<Test>::InsufficientWrappedSupply
+			);
+			// every holder can unwrap, even when less than the existential deposit remains locked
+			assert_ok!(Erc20::unwrap(Origin::signed(1), 39_000_000.into()));
+			assert_eq!(Balances::free_balance(1), 989);
+			assert_eq!(Balances::free_balance(pallet), 6);
+			assert_ok!(Erc20::unwrap(Origin::signed(2), 1_000_000.into()));
+			assert_eq!(Balances::free_balance(2), 11);
+			assert_eq!(Balances::free_balance(pallet), 5);
+			assert_eq!(Erc20::wrapped_supply(), U256::zero());
+			assert_supply_consistent::<Test>();
+			assert_wrapped_supply_consistent::<Test>();
+
+			assert_erc20_events!(
+				Test,
+				crate::Event::Mint { account: 1, amount: 100_000_000.into() },
+				crate::Event::Wrapped {
+					account: 1,
+					native_amount: 100,
+					amount: 100_000_000.into()
+				},
+				crate::Event::Burn { account: 1, amount: 50_000_000.into() },
+				crate::Event::Unwrapped {
+					account: 1,
+					native_amount: 50,
+					amount: 50_000_000.into()
+				},
+				crate::Event::Burn { account: 2, amount: 10_000_000.into() },
+				crate::Event::Unwrapped {
+					account: 2,
+					native_amount: 10,
+					amount: 10_000_000.into()
+				},
+				crate::Event::Burn { account: 1, amount: 39_000_000.into() },
+				crate::Event::Unwrapped {
+					account: 1,
+					native_amount: 39,
+					amount: 39_000_000.into()
+				},
+				crate::Event::Burn { account: 2, amount: 1_000_000.into() },
+				crate::Event::Unwrapped { account: 2, native_amount: 1, amount: 1_000_000.into() },
+			);
+		});
+}
+
+#[test]
+fn test_pallet_account_endowed_on_upgrade() {
+	let pallet = Erc20::account_id();
+
+	ExtBuilder::<Test>::default().build().execute_with(|| {
+		let _ = Balances::deposit_creating(&1, 1_000);
+		// a chain started without the endowment, on which 100 native tokens were wrapped
+		let _ = Balances::make_free_balance_be(&pallet, 100);
+		<crate::WrappedSupply<Test>>::put(U256::from(100_000_000));
+		assert_ok!(Erc20::mint(1, 100_000_000.into()));
+
+		// the last native tokens can't be unwrapped, as the account would be reaped
+		assert_noop!(
+			Erc20::unwrap(Origin::signed(1), 100_000_000.into()),
+			pallet_balances::Error::<Test>::KeepAlive
+		);
+
+		Erc20::on_runtime_upgrade();
+		assert_eq!(Balances::free_balance(pallet), 105);
+		assert_wrapped_supply_consistent::<Test>();
+		// the next upgrades leave it as it is
+		Erc20::on_runtime_upgrade();
+		assert_eq!(Balances::free_balance(pallet), 105);
+
+		assert_ok!(Erc20::unwrap(Origin::signed(1), 100_000_000.into()));
+		assert_eq!(Balances::free_balance(1), 1_100);
+		assert_eq!(Balances::free_balance(pallet), 5);
+		assert_wrapped_supply_consistent::<Test>();
+	});
+}
+
+#[test]
+fn test_wrap_errors() {
+	ExtBuilder::<Test>::default()
+		.with_balances(vec![(1, 10.into())])
+		.build()
+		.execute_with(|| {
+			let _ = Balances::deposit_creating(&1, 1_000);
+
+			assert_noop!(
+				Erc20::wrap(Origin::signed(2), 10),
+				pallet_balances::Error::<Test>::InsufficientBalance
+			);
+			assert_noop!(
+				Erc20::wrap(Origin::signed(1), 1_000),
+				pallet_balances::Error::<Test>::KeepAlive
+			);
+
+			// the pallet account already exists, so less than the existential deposit can be
+			// wrapped
+			assert_ok!(Erc20::wrap(Origin::signed(1), 4));
+			assert_noop!(
+				Erc20::unwrap(Origin::signed(1), 1_000_001.into()),
+				Error::<Test>::InexactWrapAmount
+			);
+			assert_noop!(
+				Erc20::unwrap(Origin::signed(2), 1_000_000.into()),
+				Error::<Test>::BurnExceedsBalance
+			);
+			assert_wrapped_supply_consistent::<Test>();
+		});
+}
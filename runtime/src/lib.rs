@@ -11,11 +11,13 @@ use pallet_grandpa::{
 };
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, U256};
 use sp_runtime::{
 	create_runtime_str, generic, impl_opaque_keys,
 	traits::{AccountIdLookup, BlakeTwo256, Block as BlockT, IdentifyAccount, NumberFor, Verify},
-	transaction_validity::{TransactionSource, TransactionValidity},
+	transaction_validity::{
+		TransactionLongevity, TransactionPriority, TransactionSource, TransactionValidity,
+	},
 	ApplyExtrinsicResult, MultiSignature,
 };
 use sp_std::prelude::*;
@@ -26,12 +28,14 @@ use sp_version::RuntimeVersion;
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
 	construct_runtime, parameter_types,
-	traits::{ConstU128, ConstU32, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo},
+	traits::{
+		ConstBool, ConstU128, ConstU32, ConstU8, KeyOwnerProofSystem, Randomness, StorageInfo,
+	},
 	weights::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use pallet_balances::Call as BalancesCall;
 pub use pallet_timestamp::Call as TimestampCall;
@@ -96,9 +100,9 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// The version of the runtime specification. A full node will not attempt to use its native
 	//   runtime in substitute for the on-chain Wasm runtime unless all of `spec_name`,
 	//   `spec_version`, and `authoring_version` are the same between Wasm and native.
-	// This value is set to 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
+	// This value started at 100 to notify Polkadot-JS App (https://polkadot.js.org/apps) to use
 	//   the compatible custom types.
-	spec_version: 100,
+	spec_version: 102,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
@@ -270,6 +274,21 @@ parameter_types! {
 	pub const Name: &'static str = "Meme Coin";
 	pub const Symbol: &'static str = "MEM";
 	pub const Decimals: u8 = 18;
+	/// Decimals of the native currency, wrapped 1:1 into MEM.
+	pub const NativeDecimals: u8 = 12;
+	pub const Erc20PalletId: PalletId = PalletId(*b"py/erc20");
+	/// Scheduled ERC20 transfers can take up to 10% of the block weight.
+	pub ScheduledTransfersWeight: Weight = Perbill::from_percent(10) * BlockWeights::get().max_block;
+	pub const ProposalLifetime: BlockNumber = 7 * DAYS;
+	pub const Erc20ClaimPrefix: &'static [u8] = b"Pay MEM to the Meme Coin account:";
+	pub const Erc20EthereumPrefix: &'static [u8] = b"Meme Coin transaction:";
+	/// Unsigned ERC20 claims go before the regular transactions, and stay in the pool for an hour.
+	pub const Erc20UnsignedPriority: TransactionPriority = TransactionPriority::max_value() / 2;
+	pub const Erc20UnsignedLongevity: TransactionLongevity = HOURS as TransactionLongevity;
+	/// Free Ethereum transactions only go before the regular transactions without a tip, and each
+	/// address gets one into the blocks every minute.
+	pub const Erc20EthereumPriority: TransactionPriority = 1;
+	pub const Erc20EthereumInterval: BlockNumber = MINUTES;
 }
 
 /// Configure the pallet-erc20 in pallets/erc20.
@@ -278,6 +297,26 @@ impl pallet_erc20::Config for Runtime {
 	type Name = Name;
 	type Symbol = Symbol;
 	type Decimals = Decimals;
+	type PalletId = Erc20PalletId;
+	type MaxEscrowExpiries = ConstU32<64>;
+	type MaxScheduledTransfers = ConstU32<16>;
+	type ScheduledTransfersWeight = ScheduledTransfersWeight;
+	type MaxSignatories = ConstU32<16>;
+	type ProposalLifetime = ProposalLifetime;
+	type Signature = Signature;
+	type Signer = <Signature as Verify>::Signer;
+	type ClaimPrefix = Erc20ClaimPrefix;
+	type UnsignedPriority = Erc20UnsignedPriority;
+	type UnsignedLongevity = Erc20UnsignedLongevity;
+	type AirdropOrigin = frame_system::EnsureRoot<AccountId>;
+	// 256 words track the claims of 32768 leaves
+	type MaxSweptBitmapWords = ConstU32<256>;
+	type EthereumPrefix = Erc20EthereumPrefix;
+	type EthereumTransactionPriority = Erc20EthereumPriority;
+	type EthereumTransactionInterval = Erc20EthereumInterval;
+	type EthereumLogs = ConstBool<true>;
+	type NativeCurrency = Balances;
+	type NativeDecimals = NativeDecimals;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -328,6 +367,9 @@ pub type Executive = frame_executive::Executive<
 	AllPalletsWithSystem,
 >;
 
+#[cfg(test)]
+mod tests;
+
 #[cfg(feature = "runtime-benchmarks")]
 #[macro_use]
 extern crate frame_benchmarking;
@@ -474,6 +516,38 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_erc20_runtime_api::Erc20Api<Block, AccountId, pallet_erc20::Event<Runtime>>
+		for Runtime
+	{
+		fn total_supply() -> U256 {
+			Erc20::total_supply().unwrap_or_default()
+		}
+
+		fn balance_of(account: AccountId) -> U256 {
+			Erc20::balance_of(account).unwrap_or_default()
+		}
+
+		fn allowance(owner: AccountId, spender: AccountId) -> U256 {
+			Erc20::allowance(owner, spender).unwrap_or_default()
+		}
+
+		fn simulate_transfer(
+			from: AccountId,
+			to: AccountId,
+			amount: U256,
+			spender: Option<AccountId>,
+		) -> Result<pallet_erc20::TransferSimulationOf<Runtime>, sp_runtime::DispatchError> {
+			Erc20::simulate_transfer(from, to, amount, spender)
+		}
+
+		fn eth_call(
+			from: Option<pallet_erc20::EthereumAddress>,
+			calldata: Vec<u8>,
+		) -> Result<Vec<u8>, sp_runtime::DispatchError> {
+			Erc20::eth_call(from, &calldata)
+		}
+	}
+
 	#[cfg(feature = "runtime-benchmarks")]
 	impl frame_benchmarking::Benchmark<Block> for Runtime {
 		fn benchmark_metadata(extra: bool) -> (
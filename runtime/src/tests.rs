@@ -0,0 +1,322 @@
+//! Tests of the erc20 pallet within the real runtime: AccountId32 accounts, signed extrinsics
+//! applied through `Executive`, transaction fees and block limits.
+
+use crate::*;
+use codec::Encode;
+use frame_support::{
+	assert_ok,
+	unsigned::{TransactionSource, ValidateUnsigned},
+	weights::{DispatchClass, GetDispatchInfo, Pays},
+};
+use pallet_erc20::{EcdsaSignature, EthereumCall, EthereumPayload};
+use sp_core::{ecdsa, hexdisplay::HexDisplay, Pair};
+use sp_io::hashing::keccak_256;
+use sp_keyring::AccountKeyring;
+use sp_runtime::{
+	generic::{Era, SignedPayload},
+	traits::Header as _,
+	transaction_validity::{InvalidTransaction, TransactionValidityError},
+	ApplyExtrinsicResult, DispatchError,
+};
+
+/// Native and ERC20 balance of the pre-funded accounts, like in `testnet_genesis`
+const ENDOWMENT: u128 = 1 << 60;
+
+/// Externalities with the genesis of `testnet_genesis`, Alice and Bob being pre-funded, and
+/// block 1 initialized
+fn new_test_ext() -> sp_io::TestExternalities {
+	let endowed = vec![AccountKeyring::Alice.to_account_id(), AccountKeyring::Bob.to_account_id()];
+
+	let storage = GenesisConfig {
+		system: Default::default(),
+		balances: BalancesConfig {
+			balances: endowed.iter().cloned().map(|k| (k, ENDOWMENT)).collect(),
+		},
+		aura: Default::default(),
+		grandpa: Default::default(),
+		sudo: SudoConfig { key: Some(AccountKeyring::Alice.to_account_id()) },
+		transaction_payment: Default::default(),
+		erc_20: Erc20Config {
+			total_supply: (ENDOWMENT * endowed.len() as u128).into(),
+			balances: endowed.iter().cloned().map(|k| (k, ENDOWMENT.into())).collect(),
+			claims: vec![],
+		},
+	}
+	.build_storage()
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| {
+		Executive::initialize_block(&Header::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		))
+	});
+	ext
+}
+
+/// `call` signed by `signer` with the given nonce, without tip
+fn signed(signer: AccountKeyring, nonce: Index, call: Call) -> UncheckedExtrinsic {
+	let extra: SignedExtra = (
+		frame_system::CheckNonZeroSender::new(),
+		frame_system::CheckSpecVersion::new(),
+		frame_system::CheckTxVersion::new(),
+		frame_system::CheckGenesis::new(),
+		frame_system::CheckEra::from(Era::Immortal),
+		frame_system::CheckNonce::from(nonce),
+		frame_system::CheckWeight::new(),
+		pallet_transaction_payment::ChargeTransactionPayment::from(0),
+	);
+	let payload = SignedPayload::new(call, extra).unwrap();
+	let signature = payload.using_encoded(|payload| signer.sign(payload));
+	let (call, extra, _) = payload.deconstruct();
+
+	UncheckedExtrinsic::new_signed(call, signer.to_account_id().into(), signature.into(), extra)
+}
+
+/// `eth_sign` signature by `key` of `Erc20EthereumPrefix` followed by the hex-encoded `data`
+fn eth_sign(key: &ecdsa::Pair, data: &[u8]) -> EcdsaSignature {
+	let payload = format!(
+		"{}{}",
+		std::str::from_utf8(Erc20EthereumPrefix::get()).unwrap(),
+		HexDisplay::from(&data)
+	);
+	let message = format!("\x19Ethereum Signed Message:\n{}{}", payload.len(), payload);
+
+	EcdsaSignature(key.sign_prehashed(&keccak_256(message.as_bytes())).0)
+}
+
+/// Unsigned `ethereum_transact` of `call`, signed by the Ethereum `key`
+fn ethereum_transact(
+	key: &ecdsa::Pair,
+	nonce: u64,
+	call: EthereumCall<AccountId>,
+) -> pallet_erc20::Call<Runtime> {
+	let payload = EthereumPayload::Transaction { nonce, call: call.clone() };
+	let signature = eth_sign(key, &payload.encode());
+
+	pallet_erc20::Call::ethereum_transact { nonce, call, signature }
+}
+
+fn erc20_transfer(to: AccountKeyring, amount: u128) -> Call {
+	Call::Erc20(pallet_erc20::Call::transfer { to: to.to_account_id(), amount: amount.into() })
+}
+
+/// Fee of `xt` according to the runtime configuration: base weight, length and dispatch weight,
+/// all converted 1:1 since the multiplier stays at 1
+fn expected_fee(xt: &UncheckedExtrinsic) -> Balance {
+	let base = BlockWeights::get().get(DispatchClass::Normal).base_extrinsic;
+
+	Balance::from(base + xt.get_dispatch_info().weight) +
+		TransactionByteFee::get() * xt.encode().len() as Balance
+}
+
+fn native_balance(account: AccountKeyring) -> Balance {
+	Balances::free_balance(account.to_account_id())
+}
+
+fn erc20_balance(account: AccountKeyring) -> U256 {
+	Erc20::balance_of(account.to_account_id()).unwrap_or_default()
+}
+
+#[test]
+fn test_transfer_through_executive() {
+	new_test_ext().execute_with(|| {
+		let xt = signed(AccountKeyring::Alice, 0, erc20_transfer(AccountKeyring::Bob, 1_000));
+		let fee = expected_fee(&xt);
+		let info = xt.get_dispatch_info();
+		let len = xt.encode().len() as u32;
+
+		assert_eq!(TransactionPayment::compute_fee(len, &info, 0), fee);
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+
+		assert_eq!(erc20_balance(AccountKeyring::Alice), U256::from(ENDOWMENT - 1_000));
+		assert_eq!(erc20_balance(AccountKeyring::Bob), U256::from(ENDOWMENT + 1_000));
+		assert_eq!(Erc20::total_supply(), Some(U256::from(2 * ENDOWMENT)));
+		// the fee is paid in the native token, and never refunded since the weight is static
+		assert_eq!(native_balance(AccountKeyring::Alice), ENDOWMENT - fee);
+		assert_eq!(native_balance(AccountKeyring::Bob), ENDOWMENT);
+		assert_eq!(System::account_nonce(AccountKeyring::Alice.to_account_id()), 1);
+
+		assert!(System::events().iter().any(|record| record.event ==
+			Event::Erc20(pallet_erc20::Event::Transfer {
+				from: AccountKeyring::Alice.to_account_id(),
+				to: AccountKeyring::Bob.to_account_id(),
+				amount: 1_000.into(),
+			})));
+	});
+}
+
+#[test]
+fn test_failed_transfer_pays_fee() {
+	new_test_ext().execute_with(|| {
+		let xt =
+			signed(AccountKeyring::Bob, 0, erc20_transfer(AccountKeyring::Alice, ENDOWMENT + 1));
+		let fee = expected_fee(&xt);
+
+		assert_eq!(
+			Executive::apply_extrinsic(xt),
+			Ok(Err(DispatchError::from(pallet_erc20::Error::<Runtime>::InsufficientFunds)))
+		);
+
+		// nothing moved, but the fee was charged and the nonce bumped
+		assert_eq!(erc20_balance(AccountKeyring::Alice), U256::from(ENDOWMENT));
+		assert_eq!(erc20_balance(AccountKeyring::Bob), U256::from(ENDOWMENT));
+		assert_eq!(native_balance(AccountKeyring::Bob), ENDOWMENT - fee);
+		assert_eq!(System::account_nonce(AccountKeyring::Bob.to_account_id()), 1);
+	});
+}
+
+#[test]
+fn test_wrap_through_executive() {
+	new_test_ext().execute_with(|| {
+		let pallet = Erc20::account_id();
+		let wrap = Call::Erc20(pallet_erc20::Call::wrap { native_amount: 1_000 });
+		let xt = signed(AccountKeyring::Alice, 0, wrap);
+		let fee = expected_fee(&xt);
+
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+
+		// 1_000 of the smallest native units, with 12 decimals, are worth 1_000 * 10^6 MEM units
+		let wrapped = U256::from(1_000) * U256::exp10(6);
+		assert_eq!(native_balance(AccountKeyring::Alice), ENDOWMENT - 1_000 - fee);
+		// on top of the existential deposit endowed at genesis
+		assert_eq!(Balances::free_balance(&pallet), 1_500);
+		assert_eq!(erc20_balance(AccountKeyring::Alice), U256::from(ENDOWMENT) + wrapped);
+		assert_eq!(Erc20::wrapped_supply(), wrapped);
+
+		let unwrap = Call::Erc20(pallet_erc20::Call::unwrap { amount: wrapped });
+		let xt = signed(AccountKeyring::Alice, 1, unwrap);
+		let unwrap_fee = expected_fee(&xt);
+
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+
+		assert_eq!(native_balance(AccountKeyring::Alice), ENDOWMENT - fee - unwrap_fee);
+		assert_eq!(Balances::free_balance(&pallet), 500);
+		assert_eq!(erc20_balance(AccountKeyring::Alice), U256::from(ENDOWMENT));
+		assert_eq!(Erc20::wrapped_supply(), U256::zero());
+	});
+}
+
+#[test]
+fn test_unfunded_signer_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let xt = signed(AccountKeyring::Charlie, 0, erc20_transfer(AccountKeyring::Alice, 0));
+
+		assert_eq!(
+			Executive::apply_extrinsic(xt),
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Payment))
+		);
+	});
+}
+
+#[test]
+fn test_transfer_weight() {
+	let info = erc20_transfer(AccountKeyring::Bob, 1).get_dispatch_info();
+
+	// the declared weight of the pallet, with the database weights of the runtime, and the
+	// Ethereum addresses read for its `EthereumLog`
+	assert_eq!(info.weight, RocksDbWeight::get().reads_writes(2 + 3, 3 + 1) + 20_000);
+	assert_eq!(info.class, DispatchClass::Normal);
+	assert_eq!(info.pays_fee, Pays::Yes);
+	assert!(info.weight < BlockWeights::get().get(DispatchClass::Normal).max_extrinsic.unwrap());
+}
+
+#[test]
+fn test_bulk_transfers_fill_the_block() {
+	new_test_ext().execute_with(|| {
+		let normal = BlockWeights::get().get(DispatchClass::Normal).clone();
+		let per_transfer = normal.base_extrinsic +
+			erc20_transfer(AccountKeyring::Bob, 1).get_dispatch_info().weight;
+		let capacity = normal.max_total.unwrap() / per_transfer;
+
+		let mut nonce = 0;
+		loop {
+			let xt = signed(AccountKeyring::Alice, nonce, erc20_transfer(AccountKeyring::Bob, 1));
+			match Executive::apply_extrinsic(xt) {
+				Ok(result) => assert_ok!(result),
+				Err(e) => {
+					assert_eq!(
+						e,
+						TransactionValidityError::Invalid(InvalidTransaction::ExhaustsResources)
+					);
+					break
+				},
+			}
+			nonce += 1;
+		}
+
+		// exactly as many transfers as the normal class can hold, with all of them accounted for
+		assert_eq!(nonce as u64, capacity);
+		assert_eq!(System::block_weight().get(DispatchClass::Normal), &(capacity * per_transfer));
+		assert_eq!(erc20_balance(AccountKeyring::Bob), U256::from(ENDOWMENT + capacity as u128));
+		assert_eq!(System::account_nonce(AccountKeyring::Alice.to_account_id()), nonce);
+	});
+}
+
+#[test]
+fn test_ethereum_transactions_are_bounded() {
+	new_test_ext().execute_with(|| {
+		let alice = AccountKeyring::Alice.to_account_id();
+		let key = ecdsa::Pair::from_seed(&[1; 32]);
+		let link = eth_sign(&key, &EthereumPayload::Link { account: alice.clone() }.encode());
+		let xt = signed(
+			AccountKeyring::Alice,
+			0,
+			Call::Erc20(pallet_erc20::Call::link_ethereum_address { signature: link }),
+		);
+		assert_eq!(Executive::apply_extrinsic(xt), Ok(Ok(())));
+		let address = Erc20::linked_ethereum_address(&alice).unwrap();
+		let native = native_balance(AccountKeyring::Alice);
+
+		let transfer = |amount: u128| EthereumCall::Transfer {
+			to: AccountKeyring::Bob.to_account_id(),
+			amount: amount.into(),
+		};
+		let apply =
+			|call| Executive::apply_extrinsic(UncheckedExtrinsic::new_unsigned(Call::Erc20(call)));
+		let rejected = |error| -> ApplyExtrinsicResult {
+			Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(error)))
+		};
+
+		// the free transactions go after the operational ones and the tipped ones
+		assert_eq!(
+			Erc20::validate_unsigned(
+				TransactionSource::External,
+				&ethereum_transact(&key, 0, transfer(1))
+			)
+			.map(|valid| valid.priority),
+			Ok(Erc20EthereumPriority::get())
+		);
+		assert_eq!(apply(ethereum_transact(&key, 0, transfer(1))), Ok(Ok(())));
+
+		// whatever their nonce, no other transaction of the address gets in until the interval
+		// has elapsed
+		for nonce in 1..100 {
+			assert_eq!(
+				apply(ethereum_transact(&key, nonce, transfer(1))),
+				rejected(pallet_erc20::RATE_LIMITED_ETHEREUM_ADDRESS)
+			);
+		}
+		System::set_block_number(Erc20EthereumInterval::get());
+		assert_eq!(
+			apply(ethereum_transact(&key, 1, transfer(1))),
+			rejected(pallet_erc20::RATE_LIMITED_ETHEREUM_ADDRESS)
+		);
+
+		System::set_block_number(1 + Erc20EthereumInterval::get());
+		// the calls changing nothing never get in
+		assert_eq!(
+			apply(ethereum_transact(&key, 1, transfer(0))),
+			rejected(pallet_erc20::NO_OP_ETHEREUM_CALL)
+		);
+		assert_eq!(apply(ethereum_transact(&key, 1, transfer(1))), Ok(Ok(())));
+
+		assert_eq!(Erc20::ethereum_nonce(address), 2);
+		assert_eq!(erc20_balance(AccountKeyring::Bob), U256::from(ENDOWMENT + 2));
+		assert_eq!(native_balance(AccountKeyring::Alice), native);
+	});
+}